@@ -2,9 +2,13 @@
     The environment maps variable identifiers in our code to corresponding values.
 */
 
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
-use crate::{expressions::Expr, types::Err};
+use crate::{expressions::Expr, modules::Modules, types::Err};
 
 /// Every local scope ex: {} gets its own map for local variables/functions
 /// - to easily share the Environments we used a Rc (reference counted pointer) we can just clone
@@ -15,6 +19,10 @@ pub struct Environment {
     pub enclosing: Option<Rc<Environment>>,
     /// table to all our local variables
     pub values: RefCell<HashMap<String, Expr>>,
+    /// the names in values that got declared with 'const'
+    pub constants: RefCell<HashSet<String>>,
+    /// only set on the outermost scope: the modules its interpreter loaded
+    pub modules: Option<Rc<Modules>>,
 }
 
 impl Environment {
@@ -22,6 +30,24 @@ impl Environment {
         Environment {
             enclosing: enclosing,
             values: RefCell::new(HashMap::new()),
+            constants: RefCell::new(HashSet::new()),
+            modules: None,
+        }
+    }
+
+    /// the outermost scope of an interpreter, imports anywhere below it load into modules
+    pub fn builtins(modules: Rc<Modules>) -> Self {
+        Environment {
+            modules: Some(modules),
+            ..Environment::new(None)
+        }
+    }
+
+    /// the outermost scope, that holds the builtin natives
+    pub fn root(self: &Rc<Self>) -> Rc<Environment> {
+        match &self.enclosing {
+            Some(encl_env) => encl_env.root(),
+            None => Rc::clone(self),
         }
     }
 
@@ -34,35 +60,56 @@ impl Environment {
         self.values.borrow_mut().insert(name, val);
     }
 
+    /// declares a variable with 'var' or 'const'
+    /// - unlike define() it refuses to overwrite a constant of this scope
+    pub fn declare(&self, name: String, val: Expr, constant: bool, line: usize) -> Result<(), Err> {
+        if self.constants.borrow().contains(&name) {
+            return Err(Err::Interpreter(
+                format!("Can't redeclare constant '{name}'."),
+                line,
+            ));
+        }
+        if constant {
+            self.constants.borrow_mut().insert(name.clone());
+        }
+        self.define(name, val);
+        Ok(())
+    }
+
     // read value of a variable like 'print x'
-    pub fn get_value(&self, name: String) -> Result<Expr, Err> {
+    pub fn get_value(&self, name: String, line: usize) -> Result<Expr, Err> {
         match self.values.borrow_mut().get(&name) {
             Some(val) => Ok(val.clone()),
             None => match &self.enclosing {
                 // if we cant find it localy we try move up to parent scope:
-                Some(encl_env) => encl_env.get_value(name),
+                Some(encl_env) => encl_env.get_value(name, line),
                 None => Err(Err::Interpreter(
-                    "Undefined Variable: [TODO access line nr] .".into(),
-                    69,
+                    format!("Undefined Variable: '{name}'"),
+                    line,
                 )),
-                //None => Err(Err::Interpreter("Undefined Variable: [".to_string()+name.lexeme+"] .", name.line)),
             },
         }
     }
 
     /// assings/reassigns value to previously declared variable: 'x = 123;'
     /// - not allowed to create a new variable (without 'var' keyword -> then define() )
-    pub fn assign(&self, name: String, val: Expr) -> Result<(), Err> {
+    pub fn assign(&self, name: String, val: Expr, line: usize) -> Result<(), Err> {
         if self.values.borrow_mut().contains_key(&name) {
+            if self.constants.borrow().contains(&name) {
+                return Err(Err::Interpreter(
+                    format!("Can't assign to constant '{name}'."),
+                    line,
+                ));
+            }
             self.values.borrow_mut().insert(name, val);
             Ok(())
         } else {
             if let Some(enclosing_env) = &self.enclosing {
-                enclosing_env.assign(name, val)
+                enclosing_env.assign(name, val, line)
             } else {
                 Err(Err::Interpreter(
-                    "Can't write to Undefined Variable. [TODO access line nr]".to_string(),
-                    69,
+                    format!("Can't write to Undefined Variable: '{name}'"),
+                    line,
                 ))
             }
         }
@@ -1,9 +1,9 @@
-use std::rc::Rc;
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
 use crate::{
     environment::Environment,
-    interpreter::execute_block,
-    statements::FunctionStatement,
+    interpreter::{execute_block, values_equal},
+    statements::{FunctionStatement, Statement},
     types::{Err, TokenType},
 };
 
@@ -20,6 +20,22 @@ pub enum Expr {
     VarAssign(VarAssignExpr),
     Logical(LogicalExpr),
     FnCall(FnCallExpr),
+    Get(GetExpr),
+    Set(SetExpr),
+    This(ThisExpr),
+    Super(SuperExpr),
+    List(ListExpr),
+    Index(IndexExpr),
+    IndexSet(IndexSetExpr),
+    Map(MapExpr),
+    Lambda(LambdaExpr),
+    Interpolation(InterpolationExpr),
+    CompoundAssign(CompoundAssignExpr),
+    Conditional(ConditionalExpr),
+    Match(MatchExpr),
+    Range(RangeExpr),
+    Spread(SpreadExpr),
+    DestructureAssign(DestructureAssignExpr),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -27,12 +43,14 @@ pub struct BinaryExpr {
     pub left: Box<Expr>,
     pub token: TokenType,
     pub right: Box<Expr>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnaryExpr {
     pub token: TokenType,
     pub right: Box<Expr>,
+    pub line: usize,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -44,10 +62,11 @@ pub struct GroupingExpr {
 #[derive(Debug, Clone, PartialEq)]
 pub struct VarReadExpr {
     pub name: String,
+    pub line: usize,
 }
 
 /// 'or' expression -> this shortcircuits (function calls have no side-effects)
-/// so we handle them separate
+/// so we handle them separate. 'a ?? b' only evaluates b if a is nil
 #[derive(Debug, Clone, PartialEq)]
 pub struct LogicalExpr {
     pub left: Box<Expr>,
@@ -55,17 +74,108 @@ pub struct LogicalExpr {
     pub right: Box<Expr>,
 }
 
+/// 'condition ? then_ : else_' only evaluates the branch that gets picked
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConditionalExpr {
+    pub condition: Box<Expr>,
+    pub then_: Box<Expr>,
+    pub else_: Box<Expr>,
+}
+
+/// 'match (subject) { pattern => body, ... }' runs the first arm whose pattern (and guard) fits
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchExpr {
+    pub subject: Box<Expr>,
+    pub arms: Vec<MatchArm>,
+    pub line: usize,
+}
+
+/// 'pattern if guard => body' the guard can use the names bound by the pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchArm {
+    pub pattern: Pattern,
+    pub guard: Option<Expr>,
+    pub body: ArmBody,
+}
+
+/// an arm either produces the value of its expression, or runs a block and produces nil
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArmBody {
+    Expr(Expr),
+    Block(Vec<Result<Statement, Err>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    /// '_' matches anything
+    Wildcard,
+    /// 1, "x", true or nil match values equal to them
+    Literal(Value),
+    /// a name matches anything and binds the value to it
+    Binding(String),
+    /// '[a, 1, _]' matches lists of the same length, element by element
+    List(Vec<Pattern>),
+    /// '1 | 2 | 3' matches if any of the alternatives does
+    Alternatives(Vec<Pattern>),
+    /// 'Shape.Circle(r)' matches that variant and its fields, 'Shape.Circle' ignores the fields
+    Variant {
+        enum_name: String,
+        variant: String,
+        fields: Option<Vec<Pattern>>,
+    },
+}
+
+/// 'start..end' or 'start..=end', optionally with 'step n'. Evaluates to a Value::Range
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeExpr {
+    pub start: Box<Expr>,
+    pub end: Box<Expr>,
+    pub step: Option<Box<Expr>>,
+    pub inclusive: bool,
+    pub line: usize,
+}
+
+/// '...xs' inside the arguments of a call, passes each value of xs as its own argument
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpreadExpr {
+    pub value: Box<Expr>,
+    pub line: usize,
+}
+
+/// the left side of 'var [a, b, ...rest] = xs;', 'var {name, age: years} = record;' or '[a, b] = [b, a];'
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destructure {
+    Name(String),
+    /// '[a, [b, c], ...rest]' matches a list element by element, the rest gets the left over ones
+    List {
+        elements: Vec<Destructure>,
+        rest: Option<String>,
+    },
+    /// '{name, age: years}' reads keys of a map or fields of an instance
+    Map(Vec<(String, Destructure)>),
+}
+
+/// '[a, b] = [b, a]' the whole right side gets evaluated before anything is assigned
+#[derive(Debug, Clone, PartialEq)]
+pub struct DestructureAssignExpr {
+    pub target: Destructure,
+    pub value: Box<Expr>,
+    pub line: usize,
+}
+
 /// writes to a local or global variable. 'x = 123;'
 #[derive(Debug, Clone, PartialEq)]
 pub struct VarAssignExpr {
     pub name: String,
     pub value: Box<Expr>,
+    pub line: usize,
 }
 impl VarAssignExpr {
-    pub fn new(name: String, value: Expr) -> Self {
+    pub fn new(name: String, value: Expr, line: usize) -> Self {
         VarAssignExpr {
             name: name,
             value: Box::new(value),
+            line,
         }
     }
 }
@@ -75,81 +185,738 @@ pub struct FnCallExpr {
     pub callee: Box<Expr>,
     pub paren: TokenType,
     pub arguments: Vec<Expr>,
+    /// 'name: value' arguments, they always come after the positional ones
+    pub named: Vec<(String, Expr)>,
+    pub line: usize,
+}
+
+/// reads a property of an instance: 'obj.field' or 'obj.method'
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetExpr {
+    pub object: Box<Expr>,
+    pub name: String,
+    pub line: usize,
+}
+
+/// writes to a field of an instance: 'obj.field = 123;'
+#[derive(Debug, Clone, PartialEq)]
+pub struct SetExpr {
+    pub object: Box<Expr>,
+    pub name: String,
+    pub value: Box<Expr>,
+    pub line: usize,
+}
+
+/// the 'this' keyword inside a method, resolves to the instance the method is bound to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThisExpr {
+    pub line: usize,
+}
+
+/// 'super.method' inside a subclass, looks up the method starting at the superclass
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuperExpr {
+    pub method: String,
+    pub line: usize,
+}
+
+/// a list literal like '[1, 2, x+1]'. Elements get evaluated when the literal is reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListExpr {
+    pub elements: Vec<Expr>,
+}
+
+/// a map literal like '{"a": 1, 2: "b"}'. Keys and values get evaluated when the literal is reached.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapExpr {
+    pub entries: Vec<(Expr, Expr)>,
+    pub line: usize,
+}
+
+/// reads one element of a list: 'xs[1]' or from the back with negative indices: 'xs[-1]'
+/// - also reads the value of a key in a map: 'm["key"]'
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexExpr {
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+    pub line: usize,
 }
 
+/// writes to one element of a list: 'xs[1] = 123;' or inserts into a map: 'm["key"] = 123;'
 #[derive(Debug, Clone, PartialEq)]
+pub struct IndexSetExpr {
+    pub object: Box<Expr>,
+    pub index: Box<Expr>,
+    pub value: Box<Expr>,
+    pub line: usize,
+}
+
+/// an anonymous function 'fun (a, b) { return a + b; }' that captures the env it gets evaluated in
+#[derive(Debug, Clone, PartialEq)]
+pub struct LambdaExpr {
+    pub function_st: FunctionStatement,
+}
+
+/// 'target += value' (or -= *= /=) and '++target' or 'target--' that add/subtract 1.
+/// The target is a variable, a field 'obj.name' or an index 'xs[i]', its parts get evaluated only once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompoundAssignExpr {
+    pub target: Box<Expr>,
+    /// the binary operator that combines the old value with value: '+' for '+=' or '++'
+    pub operator: TokenType,
+    pub value: Box<Expr>,
+    /// 'x++' evaluates to the old value, everything else to the new one
+    pub postfix: bool,
+    pub line: usize,
+}
+
+/// a string with embedded expressions "a: ${x}". The parts alternate between strings and expressions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterpolationExpr {
+    pub parts: Vec<Expr>,
+}
+
+#[derive(Debug, Clone)]
 pub enum Value {
     Boolean(bool),
     Nil,
     String(String),
     Number(f64),
+    /// whole number literals like '12' or '0xFF'. Mixed with a Number they get promoted to a float
+    Integer(i64),
     Callable(Rc<Function>),
-    //Instance(Rc<Instance>),
+    Class(Rc<Class>),
+    Instance(Rc<Instance>),
+    /// lists are shared by reference, so 'var b = a; b[0] = 1;' also changes a
+    List(Rc<RefCell<Vec<Expr>>>),
+    /// maps are shared by reference, just like lists
+    Map(Rc<RefCell<OrderedMap>>),
+    /// the namespace 'import "file.lox" as name;' binds to name
+    Module(Rc<Module>),
+    /// '0..10 step 2' only holds its bounds, the numbers get produced while iterating
+    Range(Range),
+    /// 'enum Shape {...}' binds Shape to this, its variants are read with 'Shape.Circle'
+    Enum(Rc<Enum>),
+    /// a tagged value like 'Shape.Circle(2)' or 'Shape.Empty'
+    Variant(Rc<Variant>),
 }
 
-#[derive(Debug, Clone, PartialEq)]
+// Lists and maps can contain themselves, deep_equal() keeps comparing them from recursing forever.
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Value::Boolean(l), Value::Boolean(r)) => l == r,
+            (Value::Nil, Value::Nil) => true,
+            (Value::String(l), Value::String(r)) => l == r,
+            (Value::Number(l), Value::Number(r)) => l == r,
+            (Value::Integer(l), Value::Integer(r)) => l == r,
+            (Value::Callable(l), Value::Callable(r)) => l == r,
+            (Value::Class(l), Value::Class(r)) => l == r,
+            (Value::Instance(l), Value::Instance(r)) => l == r,
+            (Value::List(_), Value::List(_)) | (Value::Map(_), Value::Map(_)) => {
+                let (left, right) = (Expr::Literal(self.clone()), Expr::Literal(other.clone()));
+                deep_equal(&left, &right, |l, r| l == r, &mut vec![])
+            }
+            (Value::Module(l), Value::Module(r)) => l == r,
+            (Value::Range(l), Value::Range(r)) => l == r,
+            (Value::Enum(l), Value::Enum(r)) => l == r,
+            (Value::Variant(l), Value::Variant(r)) => l == r,
+            _ => false,
+        }
+    }
+}
+
+/// Only values that can be hashed are allowed as keys in a map.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Boolean(bool),
+    Nil,
+    String(String),
+    /// whole floats become an Integer key, so 'm[1]' and 'm[1.0]' are the same key
+    Integer(i64),
+    /// the bits of the f64
+    Number(u64),
+}
+impl MapKey {
+    /// returns None for values that can not be used as a key (lists, instances, NaN...)
+    pub fn new(key: &Expr) -> Option<MapKey> {
+        match key {
+            Expr::Literal(Value::Boolean(b)) => Some(MapKey::Boolean(*b)),
+            Expr::Literal(Value::Nil) => Some(MapKey::Nil),
+            Expr::Literal(Value::String(s)) => Some(MapKey::String(s.clone())),
+            Expr::Literal(Value::Integer(n)) => Some(MapKey::Integer(*n)),
+            Expr::Literal(Value::Number(n)) if n.is_nan() => None,
+            Expr::Literal(Value::Number(n))
+                if n.fract() == 0.0 && *n >= i64::MIN as f64 && *n < i64::MAX as f64 =>
+            {
+                Some(MapKey::Integer(*n as i64))
+            }
+            // -0.0 and 0.0 should land on the same key:
+            Expr::Literal(Value::Number(n)) => Some(MapKey::Number((n + 0.0).to_bits())),
+            _ => None,
+        }
+    }
+
+    pub fn to_expr(&self) -> Expr {
+        match self {
+            MapKey::Boolean(b) => Expr::Literal(Value::Boolean(*b)),
+            MapKey::Nil => Expr::Literal(Value::Nil),
+            MapKey::String(s) => Expr::Literal(Value::String(s.clone())),
+            MapKey::Integer(n) => Expr::Literal(Value::Integer(*n)),
+            MapKey::Number(bits) => Expr::Literal(Value::Number(f64::from_bits(*bits))),
+        }
+    }
+}
+
+/// A range of integers. A negative step counts down: '10..0 step -1'
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Range {
+    pub start: i64,
+    pub end: i64,
+    pub step: i64,
+    pub inclusive: bool,
+}
+impl Range {
+    /// checks n against the end of the range (in the direction of the step)
+    pub fn before_end(&self, n: i64) -> bool {
+        match (self.step > 0, self.inclusive) {
+            (true, true) => n <= self.end,
+            (true, false) => n < self.end,
+            (false, true) => n >= self.end,
+            (false, false) => n > self.end,
+        }
+    }
+
+    /// true if iterating the range would produce n
+    pub fn contains(&self, n: i64) -> bool {
+        let from_start = match self.step > 0 {
+            true => n >= self.start,
+            false => n <= self.start,
+        };
+        from_start
+            && self.before_end(n)
+            && (n as i128 - self.start as i128) % self.step as i128 == 0
+    }
+
+    pub fn len(&self) -> usize {
+        if !self.before_end(self.start) {
+            return 0;
+        }
+        let mut distance = (self.end as i128 - self.start as i128).abs();
+        if !self.inclusive {
+            distance -= 1;
+        }
+        (distance / (self.step as i128).abs() + 1) as usize
+    }
+}
+
+/// A hashmap that remembers insertion order. Iterating or printing a map always
+/// visits the keys in the order they were first inserted.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap {
+    entries: Vec<(MapKey, Expr)>,
+    indices: HashMap<MapKey, usize>,
+}
+impl OrderedMap {
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn get(&self, key: &MapKey) -> Option<&Expr> {
+        self.indices.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    pub fn contains(&self, key: &MapKey) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// overwriting an existing key keeps its original position
+    pub fn insert(&mut self, key: MapKey, value: Expr) {
+        match self.indices.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &MapKey) -> Option<Expr> {
+        let i = self.indices.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        // every entry behind the removed one moved one slot to the front:
+        for (k, _) in &self.entries[i..] {
+            self.indices.insert(k.clone(), self.indices[k] - 1);
+        }
+        Some(value)
+    }
+
+    /// (key, value) pairs in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &(MapKey, Expr)> {
+        self.entries.iter()
+    }
+}
+impl PartialEq for OrderedMap {
+    /// two maps are equal if they hold the same key-value pairs, no matter the order
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+/// How many arguments a function accepts
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arity {
+    /// exactly n arguments
+    Fixed(usize),
+    /// at least n arguments, any more are fine
+    Variadic(usize),
+    /// from min up to max arguments, like functions with default parameters
+    Between(usize, usize),
+}
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        match self {
+            Arity::Fixed(n) => count == *n,
+            Arity::Variadic(min) => count >= *min,
+            Arity::Between(min, max) => *min <= count && count <= *max,
+        }
+    }
+}
+impl std::fmt::Display for Arity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Arity::Fixed(n) => n.fmt(f),
+            Arity::Variadic(min) => write!(f, "at least {min}"),
+            Arity::Between(min, max) => write!(f, "{min} to {max}"),
+        }
+    }
+}
+
+/// The Rust side of a native function. Gets the already evaluated arguments.
+/// - an Err(message) becomes a runtime error in the line of the call
+pub type NativeFn = Rc<dyn Fn(&[Value]) -> Result<Value, String>>;
+
+#[derive(Clone)]
 pub enum Function {
     Native {
-        arity: usize,
-        func: fn() -> Result<Value, Err>,
+        name: String,
+        arity: Arity,
+        func: NativeFn,
     },
     Declared {
         function_st: FunctionStatement,
         closure: Rc<Environment>,
+        /// the 'init' method of a class always returns 'this'
+        is_initializer: bool,
     },
 }
 impl Function {
-    pub fn arity(&self) -> usize {
+    pub fn arity(&self) -> Arity {
         match self {
-            Function::Native { arity, func: _ } => *arity,
+            Function::Native { arity, .. } => *arity,
+            Function::Declared { function_st, .. } => {
+                let params = &function_st.params;
+                let required = params
+                    .iter()
+                    .filter(|p| p.default.is_none() && !p.rest)
+                    .count();
+                if params.last().is_some_and(|p| p.rest) {
+                    return Arity::Variadic(required);
+                }
+                match required == params.len() {
+                    true => Arity::Fixed(params.len()),
+                    false => Arity::Between(required, params.len()),
+                }
+            }
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Function::Native { name, .. } => name,
+            Function::Declared { function_st, .. } => &function_st.name,
+        }
+    }
+
+    /// creates a copy of this method, where 'this' is defined in a new closure-env pointing to the instance
+    pub fn bind(&self, instance: Rc<Instance>) -> Function {
+        match self {
+            Function::Native { .. } => self.clone(),
             Function::Declared {
                 function_st,
-                closure: _,
+                closure,
+                is_initializer,
             } => {
-                let FunctionStatement {
-                    name: _,
-                    params,
-                    body: _,
-                } = function_st;
-                return params.len();
+                let this_env = Environment::new(Some(Rc::clone(closure)));
+                this_env.define("this".into(), Expr::Literal(Value::Instance(instance)));
+                Function::Declared {
+                    function_st: function_st.clone(),
+                    closure: Rc::new(this_env),
+                    is_initializer: *is_initializer,
+                }
             }
         }
     }
+
     pub fn call(
         &self,
         _env: Rc<Environment>, // TODO: we probably can remove env? maybe do methods first? do they use this call?
         arguments: Vec<Result<Expr, Err>>,
+        named: Vec<(String, Expr)>,
+        line: usize,
     ) -> Result<Expr, Err> {
         match self {
-            Function::Native { arity: _, func } => {
+            Function::Native { func, name, .. } => {
                 // call() on Native functions just execuates the callback we stored in our map
-                return Ok(Expr::Literal(func()?));
+                if !named.is_empty() {
+                    return Err(Err::Interpreter(
+                        format!("Native function '{name}' takes no named arguments."),
+                        line,
+                    ));
+                }
+                let mut values = Vec::new();
+                for arg in arguments {
+                    if let Expr::Literal(value) = arg? {
+                        values.push(value);
+                    }
+                }
+                match func(&values) {
+                    Ok(value) => Ok(Expr::Literal(value)),
+                    Err(message) => Err(Err::Interpreter(message, line)),
+                }
             }
             Function::Declared {
                 function_st,
                 closure,
+                is_initializer,
             } => {
-                // call() on Normal Functions (not Methods etc.)
+                // call() on Normal Functions and Methods
                 let FunctionStatement {
-                    name: _name,
-                    params,
-                    body,
+                    name, params, body, ..
                 } = function_st;
                 let this_env = Rc::new(Environment::new(Some(Rc::clone(closure)))); // create new local-env for this function
-                for i in 0..params.len() {
-                    // we take arguments and write them to local env, so body can access them:
-                    this_env.define(params[i].clone(), arguments[i].clone()?)
+                let (params, rest) = match params.split_last() {
+                    Some((last, params)) if last.rest => (params, Some(last)),
+                    _ => (&params[..], None),
+                };
+                // positional arguments fill the parameters from the front, named ones by their name:
+                let mut values: Vec<Option<Expr>> = vec![None; params.len()];
+                let mut rest_values = Vec::new();
+                for (i, arg) in arguments.into_iter().enumerate() {
+                    match i < params.len() {
+                        true => values[i] = Some(arg?),
+                        false => rest_values.push(arg?),
+                    }
+                }
+                for (arg_name, value) in named {
+                    let Some(i) = params.iter().position(|p| p.name == arg_name) else {
+                        return Err(Err::Interpreter(
+                            format!("Unknown argument '{arg_name}' for '{name}'."),
+                            line,
+                        ));
+                    };
+                    if values[i].is_some() {
+                        return Err(Err::Interpreter(
+                            format!("Argument '{arg_name}' given more than once."),
+                            line,
+                        ));
+                    }
+                    values[i] = Some(value);
+                }
+                // we write them to local env, so body (and later defaults) can access them:
+                for (param, value) in params.iter().zip(values) {
+                    let value = match (value, &param.default) {
+                        (Some(value), _) => value,
+                        (None, Some(default)) => default.evaluated(this_env.clone())?,
+                        (None, None) => {
+                            return Err(Err::Interpreter(
+                                format!("Missing argument '{}' for '{name}'.", param.name),
+                                line,
+                            ))
+                        }
+                    };
+                    this_env.define(param.name.clone(), value);
+                }
+                if let Some(rest) = rest {
+                    let list = Value::List(Rc::new(RefCell::new(rest_values)));
+                    this_env.define(rest.name.clone(), Expr::Literal(list));
                 }
                 // we catch the upcoming return value wrapped in an error
-                if let Err(Err::ReturnValue(val)) = execute_block(this_env, body.clone()) {
-                    return Ok(val);
+                let result = match execute_block(this_env, body.clone()) {
+                    Err(Err::ReturnValue(val)) => val,
+                    Err(e) => return Err(e), // runtime errors keep unwinding up to the next try-catch
+                    Ok(()) => Expr::Literal(Value::Nil), // or use default nil if no return value
+                };
+                match is_initializer {
+                    // even a bare 'return;' inside init() returns the instance
+                    true => closure.get_value("this".into(), line),
+                    false => Ok(result),
                 }
-                return Ok(Expr::Literal(Value::Nil)); // or use default nil if no return value
             }
         }
     }
 }
 
+/// A class declared by 'class Name { ...methods }'. Calling it creates a new Instance.
+pub struct Class {
+    pub name: String,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<String, Function>,
+}
+impl Class {
+    /// calling the class takes the same arguments as its 'init' method
+    pub fn arity(&self) -> Arity {
+        match self.find_method("init") {
+            Some(initializer) => initializer.arity(),
+            None => Arity::Fixed(0),
+        }
+    }
+
+    /// creates a new instance, then runs 'init(...arguments)' on it if there is one
+    pub fn call(
+        self: &Rc<Self>,
+        env: Rc<Environment>,
+        arguments: Vec<Result<Expr, Err>>,
+        named: Vec<(String, Expr)>,
+        line: usize,
+    ) -> Result<Expr, Err> {
+        let instance = Rc::new(Instance {
+            class: Rc::clone(self),
+            fields: RefCell::new(HashMap::new()),
+        });
+        if let Some(initializer) = self.find_method("init") {
+            initializer
+                .bind(Rc::clone(&instance))
+                .call(env, arguments, named, line)?;
+        }
+        Ok(Expr::Literal(Value::Instance(instance)))
+    }
+
+    /// looks for the method in this class first, then walks up the inheritance chain
+    pub fn find_method(&self, name: &str) -> Option<Function> {
+        match self.methods.get(name) {
+            Some(method) => Some(method.clone()),
+            None => match &self.superclass {
+                Some(superclass) => superclass.find_method(name),
+                None => None,
+            },
+        }
+    }
+}
+
+/// A runtime instance of a class. Fields can be added freely at any time: 'obj.newfield = 1;'
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: RefCell<HashMap<String, Expr>>,
+}
+impl Instance {
+    /// fields shadow methods. Methods get bound to this instance, so 'this' works inside them.
+    pub fn get(self: &Rc<Self>, name: &str, line: usize) -> Result<Expr, Err> {
+        if let Some(value) = self.fields.borrow().get(name) {
+            return Ok(value.clone());
+        }
+        if let Some(method) = self.class.find_method(name) {
+            let bound = method.bind(Rc::clone(self));
+            return Ok(Expr::Literal(Value::Callable(Rc::new(bound))));
+        }
+        Err(Err::Interpreter(
+            format!("Undefined property '{name}'."),
+            line,
+        ))
+    }
+
+    pub fn set(&self, name: String, value: Expr) {
+        self.fields.borrow_mut().insert(name, value);
+    }
+}
+
+/// A loaded lox file. Its top-level bindings are read with 'name.binding'.
+pub struct Module {
+    pub name: String,
+    /// the scope the top-level statements of the file ran in
+    pub env: Rc<Environment>,
+}
+impl Module {
+    pub fn get(&self, name: &str, line: usize) -> Result<Expr, Err> {
+        match self.env.values.borrow().get(name) {
+            Some(value) => Ok(value.clone()),
+            None => Err(Err::Interpreter(
+                format!("Module '{}' has no export '{name}'.", self.name),
+                line,
+            )),
+        }
+    }
+}
+
+/// 'enum Shape { Circle(r), Rect(w, h), Empty }'
+pub struct Enum {
+    pub name: String,
+    /// the name of each variant and of its fields, in declaration order
+    pub variants: Vec<(String, Vec<String>)>,
+}
+impl Enum {
+    /// 'Shape.Circle' is a constructor taking the fields, 'Shape.Empty' (no fields) already is the value
+    pub fn get(self: &Rc<Self>, name: &str, line: usize) -> Result<Expr, Err> {
+        let Some(index) = self.variant_index(name) else {
+            return Err(Err::Interpreter(
+                format!("Enum '{}' has no variant '{name}'.", self.name),
+                line,
+            ));
+        };
+        let fields = &self.variants[index].1;
+        if fields.is_empty() {
+            return Ok(Expr::Literal(Value::Variant(Rc::new(Variant {
+                enum_: Rc::clone(self),
+                index,
+                values: vec![],
+            }))));
+        }
+        let enum_ = Rc::clone(self);
+        let func: NativeFn = Rc::new(move |args: &[Value]| {
+            Ok(Value::Variant(Rc::new(Variant {
+                enum_: Rc::clone(&enum_),
+                index,
+                values: args.iter().cloned().map(Expr::Literal).collect(),
+            })))
+        });
+        Ok(Expr::Literal(Value::Callable(Rc::new(Function::Native {
+            name: format!("{}.{name}", self.name),
+            arity: Arity::Fixed(fields.len()),
+            func,
+        }))))
+    }
+
+    pub fn variant_index(&self, name: &str) -> Option<usize> {
+        self.variants
+            .iter()
+            .position(|(variant, _)| variant == name)
+    }
+}
+
+/// A value of one variant of an enum. Its fields are read by name: 'circle.r'
+pub struct Variant {
+    pub enum_: Rc<Enum>,
+    /// which of the variants of the enum this is
+    pub index: usize,
+    pub values: Vec<Expr>,
+}
+impl Variant {
+    pub fn name(&self) -> &str {
+        &self.enum_.variants[self.index].0
+    }
+
+    pub fn get(&self, name: &str, line: usize) -> Result<Expr, Err> {
+        let fields = &self.enum_.variants[self.index].1;
+        match fields.iter().position(|field| field == name) {
+            Some(i) => Ok(self.values[i].clone()),
+            None => Err(Err::Interpreter(
+                format!(
+                    "Variant '{}.{}' has no field '{name}'.",
+                    self.enum_.name,
+                    self.name()
+                ),
+                line,
+            )),
+        }
+    }
+}
+
+// Functions can reference themselves trough their closure and natives are opaque Rust closures.
+// So we compare them by identity, and only print their names for debugging (no endless recursion).
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Function::Native { func: l, .. }, Function::Native { func: r, .. }) => {
+                Rc::ptr_eq(l, r)
+            }
+            (
+                Function::Declared {
+                    function_st: l_st,
+                    closure: l_env,
+                    ..
+                },
+                Function::Declared {
+                    function_st: r_st,
+                    closure: r_env,
+                    ..
+                },
+            ) => Rc::ptr_eq(l_env, r_env) && l_st == r_st,
+            _ => false,
+        }
+    }
+}
+impl std::fmt::Debug for Function {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Function::Native { name, .. } => write!(f, "<native fn {name}>"),
+            Function::Declared { function_st, .. } => write!(f, "<fn {}>", function_st.name),
+        }
+    }
+}
+
+// A class is one declaration and an instance is one object, even if their contents match.
+// Their method closures can also lead back to them, so Debug only prints the class name.
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+impl PartialEq for Module {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+impl PartialEq for Enum {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+// variants are plain data, so two of them are equal if they hold the same fields
+impl PartialEq for Variant {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.enum_, &other.enum_)
+            && self.index == other.index
+            && self
+                .values
+                .iter()
+                .zip(&other.values)
+                .all(|(l, r)| values_equal(l, r))
+    }
+}
+impl std::fmt::Debug for Class {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Class({})", self.name)
+    }
+}
+impl std::fmt::Debug for Instance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Instance({})", self.class.name)
+    }
+}
+impl std::fmt::Debug for Module {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Module({})", self.name)
+    }
+}
+impl std::fmt::Debug for Enum {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Enum({})", self.name)
+    }
+}
+impl std::fmt::Debug for Variant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Variant({}.{})", self.enum_.name, self.name())
+    }
+}
+
 // Display Trait used for pretty-printing the ast tree:
 impl std::fmt::Display for Expr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -159,27 +926,326 @@ impl std::fmt::Display for Expr {
             Expr::Literal(Value::Nil) => f.write_str("nil"),
             Expr::Literal(Value::String(s)) => s.fmt(f),
             Expr::Literal(Value::Number(n)) => n.fmt(f),
-            Expr::Literal(Value::Callable(n)) => write!(f, "{:#?}", n),
-
-            Expr::Binary(BinaryExpr { left, token, right }) => {
-                f.write_fmt(format_args!("<{left} {token} {right}>"))
+            Expr::Literal(Value::Integer(n)) => n.fmt(f),
+            Expr::Literal(Value::Callable(n)) => write!(f, "{:?}", n),
+            Expr::Literal(Value::Class(class)) => class.name.fmt(f),
+            Expr::Literal(Value::Instance(instance)) => {
+                write!(f, "{} instance", instance.class.name)
+            }
+            Expr::Literal(Value::Module(module)) => write!(f, "<module {}>", module.name),
+            Expr::Literal(Value::Enum(enum_)) => enum_.name.fmt(f),
+            Expr::Literal(Value::Variant(variant)) => {
+                write!(f, "{}.{}", variant.enum_.name, variant.name())?;
+                if variant.values.is_empty() {
+                    return Ok(());
+                }
+                f.write_str("(")?;
+                for (i, value) in variant.values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    value.fmt(f)?;
+                }
+                f.write_str(")")
             }
-            Expr::Unary(UnaryExpr { token, right }) => {
+            Expr::Literal(Value::Range(range)) => {
+                let dots = if range.inclusive { "..=" } else { ".." };
+                write!(f, "{}{dots}{}", range.start, range.end)?;
+                match range.step {
+                    1 => Ok(()),
+                    step => write!(f, " step {step}"),
+                }
+            }
+            Expr::Literal(Value::List(_)) | Expr::Literal(Value::Map(_)) => {
+                fmt_collection(self, f, &mut vec![])
+            }
+
+            Expr::Binary(BinaryExpr {
+                left, token, right, ..
+            }) => f.write_fmt(format_args!("<{left} {token} {right}>")),
+            Expr::Unary(UnaryExpr { token, right, .. }) => {
                 f.write_fmt(format_args!("<{token} {right}>"))
             }
             Expr::Logical(LogicalExpr { left, token, right }) => {
                 f.write_fmt(format_args!("<{left} {token} {right}>"))
             }
+            Expr::Conditional(ConditionalExpr {
+                condition,
+                then_,
+                else_,
+            }) => write!(f, "<{condition} ? {then_} : {else_}>"),
+            Expr::Match(MatchExpr { subject, arms, .. }) => {
+                write!(f, "<match {subject} {{")?;
+                for arm in arms {
+                    write!(f, " {}", arm.pattern)?;
+                    if let Some(guard) = &arm.guard {
+                        write!(f, " if {guard}")?;
+                    }
+                    match &arm.body {
+                        ArmBody::Expr(expr) => write!(f, " => {expr},")?,
+                        ArmBody::Block(_) => f.write_str(" => {...},")?,
+                    }
+                }
+                f.write_str(" }>")
+            }
+            Expr::Range(RangeExpr {
+                start,
+                end,
+                step,
+                inclusive,
+                ..
+            }) => {
+                let dots = if *inclusive { "..=" } else { ".." };
+                write!(f, "<{start}{dots}{end}")?;
+                if let Some(step) = step {
+                    write!(f, " step {step}")?;
+                }
+                f.write_str(">")
+            }
+            Expr::Spread(SpreadExpr { value, .. }) => write!(f, "...{value}"),
+            Expr::DestructureAssign(DestructureAssignExpr { target, value, .. }) => {
+                write!(f, "<{target} = {value}>")
+            }
             Expr::Grouping(GroupingExpr { expr }) => f.write_fmt(format_args!("({expr})")),
-            Expr::VarRead(VarReadExpr { name }) => name.fmt(f),
-            Expr::VarAssign(VarAssignExpr { name, value }) => {
+            Expr::VarRead(VarReadExpr { name, .. }) => name.fmt(f),
+            Expr::VarAssign(VarAssignExpr { name, value, .. }) => {
                 f.write_fmt(format_args!("<{name} = {value}>"))
             } //Expr::RuntimeErr(e) => write!(f, "RuntimeErr({:?})", e),
-            Expr::FnCall(FnCallExpr {
-                callee,
-                paren: _,
-                arguments: _,
-            }) => f.write_fmt(format_args!("{callee}()")), //_ => write!(f, "{:?}", self),             //Failback to Debug-Printing for unimplemented expressions?
+            Expr::FnCall(FnCallExpr { callee, .. }) => f.write_fmt(format_args!("{callee}()")), //_ => write!(f, "{:?}", self),             //Failback to Debug-Printing for unimplemented expressions?
+            Expr::Get(GetExpr { object, name, .. }) => write!(f, "{object}.{name}"),
+            Expr::Set(SetExpr {
+                object,
+                name,
+                value,
+                ..
+            }) => write!(f, "<{object}.{name} = {value}>"),
+            Expr::This(_) => f.write_str("this"),
+            Expr::Super(SuperExpr { method, .. }) => write!(f, "super.{method}"),
+            Expr::List(ListExpr { elements }) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Expr::Map(MapExpr { entries, .. }) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                f.write_str("}")
+            }
+            Expr::Interpolation(InterpolationExpr { parts }) => {
+                f.write_str("\"")?;
+                for part in parts {
+                    match part {
+                        Expr::Literal(Value::String(s)) => f.write_str(s)?,
+                        expr => write!(f, "${{{expr}}}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Expr::CompoundAssign(CompoundAssignExpr {
+                target,
+                operator,
+                value,
+                postfix,
+                ..
+            }) => match postfix {
+                true => write!(f, "<{target}{operator}{operator}>"),
+                false => write!(f, "<{target} {operator}= {value}>"),
+            },
+            Expr::Lambda(LambdaExpr { function_st }) => {
+                let params: Vec<String> = function_st
+                    .params
+                    .iter()
+                    .map(|p| match p.rest {
+                        true => format!("...{}", p.name),
+                        false => p.name.clone(),
+                    })
+                    .collect();
+                write!(f, "<fun ({})>", params.join(", "))
+            }
+            Expr::Index(IndexExpr { object, index, .. }) => write!(f, "{object}[{index}]"),
+            Expr::IndexSet(IndexSetExpr {
+                object,
+                index,
+                value,
+                ..
+            }) => write!(f, "<{object}[{index}] = {value}>"),
+        }
+    }
+}
+
+impl std::fmt::Display for Destructure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Destructure::Name(name) => name.fmt(f),
+            Destructure::List { elements, rest } => {
+                let mut parts: Vec<String> = elements.iter().map(|e| e.to_string()).collect();
+                if let Some(rest) = rest {
+                    parts.push(format!("...{rest}"));
+                }
+                write!(f, "[{}]", parts.join(", "))
+            }
+            Destructure::Map(entries) => {
+                let parts: Vec<String> = entries
+                    .iter()
+                    .map(|(key, target)| match target {
+                        Destructure::Name(name) if name == key => key.clone(),
+                        target => format!("{key}: {target}"),
+                    })
+                    .collect();
+                write!(f, "{{{}}}", parts.join(", "))
+            }
+        }
+    }
+}
+
+/// the (left, right) pairs of lists and maps that are being compared further up
+pub type Comparing = Vec<(*const (), *const ())>;
+
+/// compares two values, walking into lists, maps and variants on both sides at once.
+/// Everything else gets compared by scalar_eq.
+/// - a pair of collections already being compared further up counts as equal,
+///   so two lists that contain themselves don't recurse forever
+pub fn deep_equal(
+    left: &Expr,
+    right: &Expr,
+    scalar_eq: fn(&Expr, &Expr) -> bool,
+    comparing: &mut Comparing,
+) -> bool {
+    match (left, right) {
+        (Expr::Literal(Value::List(l)), Expr::Literal(Value::List(r))) => {
+            let pair = (Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ());
+            if Rc::ptr_eq(l, r) || comparing.contains(&pair) {
+                return true;
+            }
+            comparing.push(pair);
+            let (l, r) = (l.borrow(), r.borrow());
+            let equal = l.len() == r.len()
+                && l.iter()
+                    .zip(r.iter())
+                    .all(|(l, r)| deep_equal(l, r, scalar_eq, comparing));
+            comparing.pop();
+            equal
+        }
+        (Expr::Literal(Value::Map(l)), Expr::Literal(Value::Map(r))) => {
+            let pair = (Rc::as_ptr(l) as *const (), Rc::as_ptr(r) as *const ());
+            if Rc::ptr_eq(l, r) || comparing.contains(&pair) {
+                return true;
+            }
+            comparing.push(pair);
+            let (l, r) = (l.borrow(), r.borrow());
+            let equal = l.len() == r.len()
+                && l.iter().all(|(key, value)| {
+                    r.get(key)
+                        .is_some_and(|other| deep_equal(value, other, scalar_eq, comparing))
+                });
+            comparing.pop();
+            equal
+        }
+        (Expr::Literal(Value::Variant(l)), Expr::Literal(Value::Variant(r))) => {
+            Rc::ptr_eq(&l.enum_, &r.enum_)
+                && l.index == r.index
+                && l.values
+                    .iter()
+                    .zip(&r.values)
+                    .all(|(l, r)| deep_equal(l, r, scalar_eq, comparing))
+        }
+        _ => scalar_eq(left, right),
+    }
+}
+
+/// prints lists and maps. The ones already being printed further up are shown as '[...]' or '{...}',
+/// so a list containing itself doesn't recurse forever.
+fn fmt_collection(
+    expr: &Expr,
+    f: &mut std::fmt::Formatter<'_>,
+    printing: &mut Vec<*const ()>,
+) -> std::fmt::Result {
+    match expr {
+        Expr::Literal(Value::List(list)) => {
+            let ptr = Rc::as_ptr(list) as *const ();
+            if printing.contains(&ptr) {
+                return f.write_str("[...]");
+            }
+            printing.push(ptr);
+            f.write_str("[")?;
+            for (i, element) in list.borrow().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                fmt_collection(element, f, printing)?;
+            }
+            printing.pop();
+            f.write_str("]")
+        }
+        Expr::Literal(Value::Map(map)) => {
+            let ptr = Rc::as_ptr(map) as *const ();
+            if printing.contains(&ptr) {
+                return f.write_str("{...}");
+            }
+            printing.push(ptr);
+            f.write_str("{")?;
+            for (i, (key, value)) in map.borrow().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write!(f, "{}: ", key.to_expr())?;
+                fmt_collection(value, f, printing)?;
+            }
+            printing.pop();
+            f.write_str("}")
+        }
+        other => write!(f, "{other}"),
+    }
+}
+
+impl std::fmt::Display for Pattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Pattern::Wildcard => f.write_str("_"),
+            Pattern::Literal(Value::String(s)) => write!(f, "\"{s}\""),
+            Pattern::Literal(value) => Expr::Literal(value.clone()).fmt(f),
+            Pattern::Binding(name) => name.fmt(f),
+            Pattern::List(elements) => {
+                f.write_str("[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                f.write_str("]")
+            }
+            Pattern::Alternatives(alternatives) => {
+                for (i, alternative) in alternatives.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" | ")?;
+                    }
+                    alternative.fmt(f)?;
+                }
+                Ok(())
+            }
+            Pattern::Variant {
+                enum_name,
+                variant,
+                fields,
+            } => {
+                write!(f, "{enum_name}.{variant}")?;
+                if let Some(fields) = fields {
+                    let fields: Vec<_> = fields.iter().map(|p| p.to_string()).collect();
+                    write!(f, "({})", fields.join(", "))?;
+                }
+                Ok(())
+            }
         }
     }
 }
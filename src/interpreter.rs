@@ -1,40 +1,185 @@
-use std::rc::Rc;
+use std::{cell::RefCell, cmp::Ordering, collections::HashMap, rc::Rc};
 
 use crate::{
     environment::Environment,
     expressions::{
-        BinaryExpr, Expr, Expr::*, FnCallExpr, Function, GroupingExpr, LogicalExpr, UnaryExpr,
-        Value, Value::*, VarAssignExpr, VarReadExpr,
+        deep_equal, Arity, ArmBody, BinaryExpr, Class, CompoundAssignExpr, ConditionalExpr,
+        Destructure, DestructureAssignExpr, Enum, Expr, Expr::*, FnCallExpr, Function, GetExpr,
+        GroupingExpr, IndexExpr, IndexSetExpr, Instance, InterpolationExpr, LambdaExpr, ListExpr,
+        LogicalExpr, MapExpr, MapKey, MatchExpr, NativeFn, OrderedMap, Pattern, RangeExpr, SetExpr,
+        SpreadExpr, SuperExpr, ThisExpr, UnaryExpr, Value, Value::*, VarAssignExpr, VarReadExpr,
     },
+    modules::Modules,
     statements::Statement,
     types::{Err, TokenType},
 };
 
 /// Takes the root of the AST and evaluates it down to a result.
-pub fn interpret(inputs: Vec<Result<Statement, Err>>) {
-    // envirnoment that holds reference to all variable-names-> values mapped:
-    let global_scope = build_global_scope();
-
+/// - errors that were not caught by any try-catch end up here and stop the run
+pub fn interpret(
+    inputs: Vec<Result<Statement, Err>>,
+    global_scope: Rc<Environment>,
+) -> Result<(), Err> {
     for statement in inputs {
-        exececute(global_scope.clone(), statement).unwrap(); // this will panic when we return at top level, maybe give it a proper err msg
+        exececute(global_scope.clone(), statement)?;
     }
+    Ok(())
 }
 
 /*
         To make native functions ex 'time()' accessible we inject them into the global_scope:
 */
-fn build_global_scope() -> Rc<Environment> {
-    let global_scope: Rc<Environment> = Rc::new(Environment::new(None));
+pub fn build_global_scope(modules: Rc<Modules>) -> Rc<Environment> {
+    // envirnoment that holds reference to all variable-names-> values mapped:
+    let global_scope: Rc<Environment> = Rc::new(Environment::builtins(modules));
     // next we inject our custom functions, so they become available in global scope:
-    let clock = Expr::Literal(Value::Callable(Rc::new(Function::Native {
-        arity: 0,
-        func: clock_native,
-    })));
-    global_scope.define("clock".into(), clock);
+    define_native(
+        &global_scope,
+        "clock",
+        Arity::Fixed(0),
+        Rc::new(clock_native),
+    );
+    define_native(&global_scope, "len", Arity::Fixed(1), Rc::new(len_native));
+    let error_class = ERROR_CLASS.with(Rc::clone);
+    global_scope.define("Error".into(), Literal(Value::Class(error_class)));
+    // a constant, so user code can't reassign it. 'var done' or similar names don't get in the way either
+    global_scope.define(
+        "Iterator".into(),
+        Literal(Value::Enum(ITERATOR.with(Rc::clone))),
+    );
+    global_scope
+        .constants
+        .borrow_mut()
+        .insert("Iterator".into());
     return global_scope;
 }
 
-fn clock_native() -> Result<Value, Err> {
+thread_local! {
+    /// caught runtime errors become instances of this class, with the fields 'message' and 'line'
+    static ERROR_CLASS: Rc<Class> = Rc::new(Class {
+        name: "Error".into(),
+        superclass: None,
+        methods: HashMap::new(),
+    });
+
+    /// 'Iterator.done' the value next() returns to end a 'for (x in iterator)' loop
+    static ITERATOR: Rc<Enum> = Rc::new(Enum {
+        name: "Iterator".into(),
+        variants: vec![("done".into(), vec![])],
+    });
+}
+
+/// walks over the values a 'for (item in iterable)' loop visits
+pub enum Iteration {
+    /// lists get read by index, so changes to later elements during the loop are seen
+    List(Rc<RefCell<Vec<Expr>>>, usize),
+    /// the next number of the range, None once it overflowed
+    Range(crate::expressions::Range, Option<i64>),
+    /// the characters of a string or the keys of a map, taken when the loop starts
+    Values(std::vec::IntoIter<Expr>),
+    /// user defined iterators: a callable that returns the next value, or 'Iterator.done' once exhausted
+    Protocol(Expr),
+}
+
+impl Iteration {
+    /// - instances with an 'iter()' method get iterated over by whatever it returns
+    /// - instances with a 'next()' method and plain functions are iterators themselves
+    pub fn new(iterable: Expr, env: Rc<Environment>, line: usize) -> Result<Self, Err> {
+        let mut value = iterable;
+        if let Literal(Value::Instance(instance)) = &value {
+            if let Ok(iter) = instance.get("iter", line) {
+                value = call_value(iter, vec![], vec![], env, line)?;
+            }
+        }
+        match value {
+            Literal(Value::List(list)) => Ok(Iteration::List(list, 0)),
+            Literal(Value::Range(range)) => Ok(Iteration::Range(range, Some(range.start))),
+            Literal(Value::String(s)) => {
+                let chars: Vec<Expr> = s
+                    .chars()
+                    .map(|c| Literal(Value::String(c.into())))
+                    .collect();
+                Ok(Iteration::Values(chars.into_iter()))
+            }
+            Literal(Value::Map(map)) => {
+                let keys: Vec<Expr> = map.borrow().iter().map(|(k, _)| k.to_expr()).collect();
+                Ok(Iteration::Values(keys.into_iter()))
+            }
+            Literal(Value::Instance(instance)) => match instance.get("next", line) {
+                Ok(next) => Ok(Iteration::Protocol(next)),
+                _ => Err(Err::Interpreter(
+                    format!(
+                        "Can't iterate over {} instance, it needs an iter() or next() method.",
+                        instance.class.name
+                    ),
+                    line,
+                )),
+            },
+            callable @ Literal(Value::Callable(_)) => Ok(Iteration::Protocol(callable)),
+            other => Err(Err::Interpreter(
+                format!("Can't iterate over {other}."),
+                line,
+            )),
+        }
+    }
+
+    pub fn next(&mut self, env: Rc<Environment>, line: usize) -> Result<Option<Expr>, Err> {
+        match self {
+            Iteration::List(list, index) => {
+                let item = list.borrow().get(*index).cloned();
+                *index += 1;
+                Ok(item)
+            }
+            Iteration::Range(range, current) => match *current {
+                Some(n) if range.before_end(n) => {
+                    *current = n.checked_add(range.step);
+                    Ok(Some(Literal(Integer(n))))
+                }
+                _ => Ok(None),
+            },
+            Iteration::Values(values) => Ok(values.next()),
+            Iteration::Protocol(next) => match call_value(next.clone(), vec![], vec![], env, line)?
+            {
+                Literal(Value::Variant(variant))
+                    if ITERATOR.with(|iterator| Rc::ptr_eq(iterator, &variant.enum_)) =>
+                {
+                    Ok(None)
+                }
+                item => Ok(Some(item)),
+            },
+        }
+    }
+}
+
+/// converts an error into the value a 'catch (e)' binds. None for errors that can't be caught.
+/// - 'throw value;' passes the value along unchanged
+/// - runtime errors (division by zero, undefined variables...) become an 'Error instance'
+pub fn caught_value(error: Err) -> Option<Expr> {
+    match error {
+        Err::Thrown(value, _) => Some(value),
+        Err::Interpreter(message, line) => {
+            let instance = Instance {
+                class: ERROR_CLASS.with(Rc::clone),
+                fields: RefCell::new(HashMap::new()),
+            };
+            instance.set("message".into(), Literal(Value::String(message)));
+            instance.set("line".into(), Literal(Integer(line as i64)));
+            Some(Literal(Value::Instance(Rc::new(instance))))
+        }
+        _ => None,
+    }
+}
+
+pub fn define_native(env: &Environment, name: &str, arity: Arity, func: NativeFn) {
+    let native = Expr::Literal(Value::Callable(Rc::new(Function::Native {
+        name: name.into(),
+        arity,
+        func,
+    })));
+    env.define(name.into(), native);
+}
+
+fn clock_native(_args: &[Value]) -> Result<Value, std::string::String> {
     Ok(Value::Number(get_epoch_ms()))
 }
 fn get_epoch_ms() -> f64 {
@@ -44,29 +189,29 @@ fn get_epoch_ms() -> f64 {
         .as_millis() as f64
 }
 
+/// number of elements in a list or map, or characters in a string
+fn len_native(args: &[Value]) -> Result<Value, std::string::String> {
+    match &args[0] {
+        Value::String(s) => Ok(Value::Integer(s.chars().count() as i64)),
+        Value::List(list) => Ok(Value::Integer(list.borrow().len() as i64)),
+        Value::Map(map) => Ok(Value::Integer(map.borrow().len() as i64)),
+        Value::Range(range) => Ok(Value::Integer(range.len() as i64)),
+        other => Err(format!(
+            "len() expects a string, list, map or range, not {}",
+            Literal(other.clone())
+        )),
+    }
+}
+
 /*
         Statements Execute, always end with a ;
 */
 
 fn exececute(scope: Rc<Environment>, statement: Result<Statement, Err>) -> Result<(), Err> {
-    match statement {
-        Ok(st) => {
-            // after trying execution we check if we hit an runtime error, if so we print then abort
-            if let Err(e) = st.execute(scope) {
-                if let Err::ReturnValue(value) = e.clone() {
-                    return Err(Err::ReturnValue(value)); // return values use this unwind up to the next FunctionCall
-                }
-                println!("{e}");
-                std::process::exit(1);
-            };
-            Ok(())
-        }
-        Result::Err(e) => {
-            // We hit a parsing error and print that out: (since the statement was wrong we cant even try to execute it)
-            println!("{e}");
-            std::process::exit(1);
-        }
-    }
+    // - return values use the Err to unwind up to the next FunctionCall (break/continue up to the next loop)
+    // - runtime errors unwind up to the next try-catch, or all the way up to interpret()
+    // - parsing errors also end up in interpret(), since the statement was wrong we cant even try to execute it
+    statement?.execute(scope)
 }
 
 /// gets called from Statements-'visitorpattern'
@@ -106,21 +251,272 @@ impl Expr {
 
             VarAssign(expr) => expr.eval_with_env(env),
             VarRead(expr) => expr.eval_with_env(env),
+
+            Get(expr) => expr.eval_with_env(env),
+            Set(expr) => expr.eval_with_env(env),
+            This(expr) => expr.eval_with_env(env),
+            Super(expr) => expr.eval_with_env(env),
+
+            Expr::List(expr) => expr.eval_with_env(env),
+            Index(expr) => expr.eval_with_env(env),
+            IndexSet(expr) => expr.eval_with_env(env),
+            Expr::Map(expr) => expr.eval_with_env(env),
+            Lambda(expr) => expr.eval_with_env(env),
+            Interpolation(expr) => expr.eval_with_env(env),
+            CompoundAssign(expr) => expr.eval_with_env(env),
+            Conditional(expr) => expr.eval_with_env(env),
+            Match(expr) => expr.eval_with_env(env),
+            Expr::Range(expr) => expr.eval_with_env(env),
+            DestructureAssign(expr) => expr.eval_with_env(env),
+            // the parser only creates these inside of call arguments, where the call expands them
+            Spread(SpreadExpr { line, .. }) => Err(Err::Interpreter(
+                "Can only spread '...' into call arguments or list literals.".into(),
+                *line,
+            )),
+        }
+    }
+}
+
+impl RangeExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let bound = |expr: &Expr| match expr.evaluated(env.clone())? {
+            Literal(Integer(n)) => Ok(n),
+            other => Err(Err::Interpreter(
+                format!("Range bounds and step must be integers, got {other}."),
+                self.line,
+            )),
+        };
+        let step = match &self.step {
+            Some(step) => bound(step)?,
+            None => 1,
+        };
+        let range = crate::expressions::Range {
+            start: bound(&self.start)?,
+            end: bound(&self.end)?,
+            step,
+            inclusive: self.inclusive,
+        };
+        if step == 0 {
+            return Err(Err::Interpreter("Range step can't be 0.".into(), self.line));
+        }
+        Ok(Literal(Value::Range(range)))
+    }
+}
+
+impl MatchExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let value = self.subject.evaluated(env.clone())?;
+        for arm in &self.arms {
+            // each arm gets its own scope for the names its pattern binds
+            let arm_env = Rc::new(Environment::new(Some(env.clone())));
+            if !arm.pattern.matches(&value, &arm_env, self.line)? {
+                continue;
+            }
+            if let Some(guard) = &arm.guard {
+                if !is_truthy(guard.evaluated(arm_env.clone())?) {
+                    continue;
+                }
+            }
+            return match &arm.body {
+                ArmBody::Expr(expr) => expr.evaluated(arm_env),
+                ArmBody::Block(statements) => {
+                    execute_block(arm_env, statements.clone())?;
+                    Ok(Literal(Nil))
+                }
+            };
         }
+        Err(Err::Interpreter(
+            format!("No match arm matches the value {value}."),
+            self.line,
+        ))
+    }
+}
+
+impl Pattern {
+    /// checks if the value fits the pattern, binding names into env along the way
+    /// - variant patterns look up their enum, so a misspelled variant is an error instead of no match
+    fn matches(&self, value: &Expr, env: &Rc<Environment>, line: usize) -> Result<bool, Err> {
+        match (self, value) {
+            (Pattern::Wildcard, _) => Ok(true),
+            (Pattern::Literal(literal), value) => {
+                Ok(values_equal(&Literal(literal.clone()), value))
+            }
+            (Pattern::Binding(name), value) => {
+                env.define(name.clone(), value.clone());
+                Ok(true)
+            }
+            (Pattern::List(patterns), Literal(Value::List(list))) => {
+                let list = list.borrow();
+                if list.len() != patterns.len() {
+                    return Ok(false);
+                }
+                Pattern::all_match(patterns, &list, env, line)
+            }
+            (Pattern::List(_), _) => Ok(false),
+            (Pattern::Alternatives(alternatives), value) => {
+                for alternative in alternatives {
+                    // an alternative that fails halfway must not leave its names behind
+                    let scratch = Rc::new(Environment::new(Some(Rc::clone(env))));
+                    if alternative.matches(value, &scratch, line)? {
+                        for (name, value) in scratch.values.take() {
+                            env.define(name, value);
+                        }
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            (
+                Pattern::Variant {
+                    enum_name,
+                    variant,
+                    fields,
+                },
+                value,
+            ) => {
+                let Literal(Value::Enum(enum_)) = env.get_value(enum_name.clone(), line)? else {
+                    return Err(Err::Interpreter(
+                        format!("'{enum_name}' in pattern {self} is not an enum."),
+                        line,
+                    ));
+                };
+                let Some(index) = enum_.variant_index(variant) else {
+                    return Err(Err::Interpreter(
+                        format!("Enum '{enum_name}' has no variant '{variant}'."),
+                        line,
+                    ));
+                };
+                if let Some(fields) = fields {
+                    let expected = enum_.variants[index].1.len();
+                    if fields.len() != expected {
+                        return Err(Err::Interpreter(
+                            format!("Pattern {self} needs {expected} fields."),
+                            line,
+                        ));
+                    }
+                }
+                let Literal(Value::Variant(value)) = value else {
+                    return Ok(false);
+                };
+                if !Rc::ptr_eq(&value.enum_, &enum_) || value.index != index {
+                    return Ok(false);
+                }
+                match fields {
+                    Some(fields) => Pattern::all_match(fields, &value.values, env, line),
+                    None => Ok(true),
+                }
+            }
+        }
+    }
+
+    fn all_match(
+        patterns: &[Pattern],
+        values: &[Expr],
+        env: &Rc<Environment>,
+        line: usize,
+    ) -> Result<bool, Err> {
+        for (pattern, value) in patterns.iter().zip(values) {
+            if !pattern.matches(value, env, line)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
     }
 }
 
 impl VarAssignExpr {
     fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
         let new_val = self.value.evaluated(env.clone());
-        env.assign(self.name.clone(), new_val.clone()?)?;
+        env.assign(self.name.clone(), new_val.clone()?, self.line)?;
         return new_val;
     }
 }
 
-impl VarReadExpr {
+impl DestructureAssignExpr {
     fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
-        env.get_value(self.name.clone())
+        let value = self.value.evaluated(env.clone())?;
+        self.target
+            .bind(value.clone(), self.line, &mut |name, element| {
+                env.assign(name, element, self.line)
+            })?;
+        Ok(value)
+    }
+}
+
+impl Destructure {
+    /// takes the value apart along the shape of the target, then hands each name and its part to bind
+    /// - bind either defines new variables (var declarations) or assigns to existing ones
+    pub fn bind(
+        &self,
+        value: Expr,
+        line: usize,
+        bind: &mut dyn FnMut(std::string::String, Expr) -> Result<(), Err>,
+    ) -> Result<(), Err> {
+        match (self, value) {
+            (Destructure::Name(name), value) => bind(name.clone(), value),
+            (Destructure::List { elements, rest }, Literal(Value::List(list))) => {
+                let list = list.borrow().clone();
+                let fits = match rest {
+                    Some(_) => list.len() >= elements.len(),
+                    None => list.len() == elements.len(),
+                };
+                if !fits {
+                    let at_least = if rest.is_some() { "at least " } else { "" };
+                    return Err(Err::Interpreter(
+                        format!(
+                            "Expected {at_least}{} elements to destructure but got {}.",
+                            elements.len(),
+                            list.len()
+                        ),
+                        line,
+                    ));
+                }
+                for (target, element) in elements.iter().zip(list.iter()) {
+                    target.bind(element.clone(), line, bind)?;
+                }
+                if let Some(rest) = rest {
+                    let left_over = list[elements.len()..].to_vec();
+                    bind(
+                        rest.clone(),
+                        Literal(Value::List(Rc::new(RefCell::new(left_over)))),
+                    )?;
+                }
+                Ok(())
+            }
+            (Destructure::Map(entries), Literal(Value::Map(map))) => {
+                for (key, target) in entries {
+                    let Some(element) = map.borrow().get(&MapKey::String(key.clone())).cloned()
+                    else {
+                        return Err(Err::Interpreter(
+                            format!("Missing key '{key}' to destructure."),
+                            line,
+                        ));
+                    };
+                    target.bind(element, line, bind)?;
+                }
+                Ok(())
+            }
+            (Destructure::Map(entries), Literal(Value::Instance(instance))) => {
+                for (key, target) in entries {
+                    target.bind(instance.get(key, line)?, line, bind)?;
+                }
+                Ok(())
+            }
+            (Destructure::List { .. }, value) => Err(Err::Interpreter(
+                format!("Can only destructure a list into {self}, got {value}."),
+                line,
+            )),
+            (Destructure::Map(_), value) => Err(Err::Interpreter(
+                format!("Can only destructure a map or instance into {self}, got {value}."),
+                line,
+            )),
+        }
+    }
+}
+
+impl VarReadExpr {
+    pub fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        env.get_value(self.name.clone(), self.line)
     }
 }
 
@@ -128,33 +524,365 @@ impl FnCallExpr {
     fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
         let callee = self.callee.evaluated(env.clone())?;
         let mut arguments = Vec::new();
-        for arg in self.arguments.clone() {
-            arguments.push(arg.evaluated(env.clone()))
+        for arg in &self.arguments {
+            match arg {
+                Expr::Spread(spread) => {
+                    let value = spread.value.evaluated(env.clone())?;
+                    let mut iteration = Iteration::new(value, env.clone(), spread.line)?;
+                    while let Some(item) = iteration.next(env.clone(), spread.line)? {
+                        arguments.push(Ok(item));
+                    }
+                }
+                arg => arguments.push(arg.evaluated(env.clone())),
+            }
         }
-        // check if were trying to call function or obj not like "str".do()
+        let mut named = Vec::new();
+        for (name, arg) in &self.named {
+            named.push((name.clone(), arg.evaluated(env.clone())?));
+        }
+        call_value(callee, arguments, named, env, self.line)
+    }
+}
 
-        if let Expr::Literal(Value::Callable(function)) = callee.clone() {
-            if arguments.len() != function.arity() {
-                return Err(Err::Interpreter(
-                    format!(
-                        "Expected {} arguments but got {}.",
-                        function.arity(),
-                        arguments.len()
-                    ),
-                    69,
-                ));
-            }
+/// calls a function or class value with already evaluated arguments
+fn call_value(
+    callee: Expr,
+    arguments: Vec<Result<Expr, Err>>,
+    named: Vec<(std::string::String, Expr)>,
+    env: Rc<Environment>,
+    line: usize,
+) -> Result<Expr, Err> {
+    let count = arguments.len() + named.len();
+    // check if were trying to call function or obj not like "str".do()
+    match callee {
+        Expr::Literal(Value::Callable(function)) => {
+            check_arity(function.arity(), count, line)?;
             // Functions 'throw' on Return to get here, so we match for that special return-error
-            match function.call(env.clone(), arguments) {
-                Err(Err::ReturnValue(return_val)) => return Ok(return_val),
-                res => return res,
+            match function.call(env, arguments, named, line) {
+                Err(Err::ReturnValue(return_val)) => Ok(return_val),
+                res => res,
+            }
+        }
+        Expr::Literal(Value::Class(class)) => {
+            check_arity(class.arity(), count, line)?;
+            class.call(env, arguments, named, line)
+        }
+        _ => Err(Err::Interpreter(
+            "Can only call functions and classes.".to_string(),
+            line,
+        )),
+    }
+}
+
+fn check_arity(arity: Arity, got: usize, line: usize) -> Result<(), Err> {
+    if !arity.accepts(got) {
+        return Err(Err::Interpreter(
+            format!("Expected {arity} arguments but got {got}."),
+            line,
+        ));
+    }
+    Ok(())
+}
+
+impl GetExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        match self.object.evaluated(env)? {
+            Expr::Literal(Value::Instance(instance)) => instance.get(&self.name, self.line),
+            Expr::Literal(Value::Module(module)) => module.get(&self.name, self.line),
+            Expr::Literal(Value::Enum(enum_)) => enum_.get(&self.name, self.line),
+            Expr::Literal(Value::Variant(variant)) => variant.get(&self.name, self.line),
+            _ => Err(Err::Interpreter(
+                "Only instances have properties.".to_string(),
+                self.line,
+            )),
+        }
+    }
+}
+
+impl SetExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let Expr::Literal(Value::Instance(instance)) = self.object.evaluated(env.clone())? else {
+            return Err(Err::Interpreter(
+                "Only instances have fields.".to_string(),
+                self.line,
+            ));
+        };
+        let value = self.value.evaluated(env)?;
+        instance.set(self.name.clone(), value.clone());
+        Ok(value)
+    }
+}
+
+impl SuperExpr {
+    /// looks up the method on the superclass, then binds it to the current 'this'
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let Expr::Literal(Value::Class(superclass)) = env.get_value("super".into(), self.line)?
+        else {
+            return Err(Err::Interpreter(
+                "'super' must refer to a class.".to_string(),
+                self.line,
+            ));
+        };
+        let Expr::Literal(Value::Instance(instance)) = env.get_value("this".into(), self.line)?
+        else {
+            return Err(Err::Interpreter(
+                "'this' must refer to an instance.".to_string(),
+                self.line,
+            ));
+        };
+        match superclass.find_method(&self.method) {
+            Some(method) => Ok(Expr::Literal(Value::Callable(Rc::new(
+                method.bind(instance),
+            )))),
+            None => Err(Err::Interpreter(
+                format!("Undefined property '{}'.", self.method),
+                self.line,
+            )),
+        }
+    }
+}
+
+impl CompoundAssignExpr {
+    /// reads the target, combines it with the value and writes the result back into the target
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let combine = |old: Expr| -> Result<(Expr, Expr), Err> {
+            let value = self.value.evaluated(env.clone())?;
+            let new = binary_operation(old.clone(), self.operator.clone(), value, self.line)?;
+            match self.postfix {
+                true => Ok((new, old)),
+                false => Ok((new.clone(), new)),
+            }
+        };
+        match &*self.target {
+            VarRead(var) => {
+                let old = env.get_value(var.name.clone(), var.line)?;
+                let (new, result) = combine(old)?;
+                env.assign(var.name.clone(), new, var.line)?;
+                Ok(result)
+            }
+            Get(get) => {
+                let Literal(Value::Instance(instance)) = get.object.evaluated(env.clone())? else {
+                    return Err(Err::Interpreter(
+                        "Only instances have fields.".to_string(),
+                        get.line,
+                    ));
+                };
+                let old = instance.get(&get.name, get.line)?;
+                let (new, result) = combine(old)?;
+                instance.set(get.name.clone(), new);
+                Ok(result)
+            }
+            Index(index) => {
+                let object = index.object.evaluated(env.clone())?;
+                let key = index.index.evaluated(env.clone())?;
+                let old = index_get(object.clone(), key.clone(), index.line)?;
+                let (new, result) = combine(old)?;
+                index_set(object, key, new, index.line)?;
+                Ok(result)
+            }
+            target => Err(Err::Interpreter(
+                format!("Invalid assignment target {target}."),
+                self.line,
+            )),
+        }
+    }
+}
+
+impl ConditionalExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        match is_truthy(self.condition.evaluated(env.clone())?) {
+            true => self.then_.evaluated(env),
+            false => self.else_.evaluated(env),
+        }
+    }
+}
+
+impl InterpolationExpr {
+    /// every part gets turned into text just like 'print' would do it
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let mut text = std::string::String::new();
+        for part in &self.parts {
+            text += &part.evaluated(env.clone())?.to_string();
+        }
+        Ok(Literal(Value::String(text)))
+    }
+}
+
+impl LambdaExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        Ok(Literal(Callable(Rc::new(Function::Declared {
+            function_st: self.function_st.clone(),
+            closure: env,
+            is_initializer: false,
+        }))))
+    }
+}
+
+impl ListExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let mut elements = Vec::new();
+        for element in &self.elements {
+            match element {
+                Expr::Spread(spread) => {
+                    let value = spread.value.evaluated(env.clone())?;
+                    let mut iteration = Iteration::new(value, env.clone(), spread.line)?;
+                    while let Some(item) = iteration.next(env.clone(), spread.line)? {
+                        elements.push(item);
+                    }
+                }
+                element => elements.push(element.evaluated(env.clone())?),
+            }
+        }
+        Ok(Literal(Value::List(Rc::new(RefCell::new(elements)))))
+    }
+}
+
+impl MapExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let mut map = OrderedMap::default();
+        for (key, value) in &self.entries {
+            let key = map_key(key.evaluated(env.clone())?, self.line)?;
+            map.insert(key, value.evaluated(env.clone())?);
+        }
+        Ok(Literal(Value::Map(Rc::new(RefCell::new(map)))))
+    }
+}
+
+impl IndexExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let object = self.object.evaluated(env.clone())?;
+        let index = self.index.evaluated(env)?;
+        index_get(object, index, self.line)
+    }
+
+    /// removes the element or entry from the list or map: 'delete xs[0];'
+    pub fn delete(&self, env: Rc<Environment>) -> Result<(), Err> {
+        let object = self.object.evaluated(env.clone())?;
+        let index = self.index.evaluated(env)?;
+        match object {
+            Literal(Value::List(list)) => {
+                let mut list = list.borrow_mut();
+                let i = list_index(index, list.len(), self.line)?;
+                list.remove(i);
+                Ok(())
+            }
+            Literal(Value::Map(map)) => {
+                let key = map_key(index, self.line)?;
+                match map.borrow_mut().remove(&key) {
+                    Some(_) => Ok(()),
+                    None => Err(Err::Interpreter(
+                        format!("Undefined key '{}'.", key.to_expr()),
+                        self.line,
+                    )),
+                }
+            }
+            _ => Err(Err::Interpreter(
+                format!("Can only delete from lists and maps, not {object}."),
+                self.line,
+            )),
+        }
+    }
+}
+
+impl IndexSetExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        let object = self.object.evaluated(env.clone())?;
+        let index = self.index.evaluated(env.clone())?;
+        let value = self.value.evaluated(env)?;
+        index_set(object, index, value.clone(), self.line)?;
+        Ok(value)
+    }
+}
+
+/// reads 'object[index]' from a list or map
+fn index_get(object: Expr, index: Expr, line: usize) -> Result<Expr, Err> {
+    match object {
+        Literal(Value::List(list)) => {
+            let list = list.borrow();
+            let i = list_index(index, list.len(), line)?;
+            Ok(list[i].clone())
+        }
+        Literal(Value::Map(map)) => {
+            let key = map_key(index, line)?;
+            match map.borrow().get(&key) {
+                Some(value) => Ok(value.clone()),
+                None => Err(Err::Interpreter(
+                    format!("Undefined key '{}'.", key.to_expr()),
+                    line,
+                )),
             }
         }
+        _ => Err(Err::Interpreter(
+            format!("Can only index into lists and maps, not {object}."),
+            line,
+        )),
+    }
+}
+
+/// writes 'object[index] = value' into a list or map
+fn index_set(object: Expr, index: Expr, value: Expr, line: usize) -> Result<(), Err> {
+    match object {
+        Literal(Value::List(list)) => {
+            let mut list = list.borrow_mut();
+            let i = list_index(index, list.len(), line)?;
+            list[i] = value;
+        }
+        Literal(Value::Map(map)) => {
+            let key = map_key(index, line)?;
+            map.borrow_mut().insert(key, value);
+        }
+        _ => {
+            return Err(Err::Interpreter(
+                format!("Can only index into lists and maps, not {object}."),
+                line,
+            ))
+        }
+    }
+    Ok(())
+}
+
+fn map_key(key: Expr, line: usize) -> Result<MapKey, Err> {
+    match MapKey::new(&key) {
+        Some(key) => Ok(key),
+        None => Err(Err::Interpreter(
+            format!("Map keys must be strings, numbers, booleans or nil, not {key}."),
+            line,
+        )),
+    }
+}
+
+/// maps the index into the list bounds. Negative indices count from the back: xs[-1] is the last element
+fn list_index(index: Expr, len: usize, line: usize) -> Result<usize, Err> {
+    let nr = match index {
+        Literal(Integer(nr)) => nr,
+        Literal(Number(nr)) if nr.fract() == 0.0 => nr as i64,
+        Literal(Number(nr)) => {
+            return Err(Err::Interpreter(
+                format!("List index must be a whole number, not {nr}."),
+                line,
+            ))
+        }
+        index => {
+            return Err(Err::Interpreter(
+                format!("List index must be a number, not {index}."),
+                line,
+            ))
+        }
+    };
+    let i = if nr < 0 { nr + len as i64 } else { nr };
+    if i < 0 || i >= len as i64 {
         return Err(Err::Interpreter(
-            format!("Can only call functions and classes."),
-            69,
+            format!("Index {nr} out of range for list of length {len}."),
+            line,
         ));
     }
+    Ok(i as usize)
+}
+
+impl ThisExpr {
+    fn eval_with_env(&self, env: Rc<Environment>) -> Result<Expr, Err> {
+        env.get_value("this".into(), self.line)
+    }
 }
 
 impl Evaluates for Value {
@@ -175,12 +903,20 @@ impl Evaluates for UnaryExpr {
 
         match (self.token.clone(), right?) {
             (TokenType::Minus, Literal(Number(nr))) => Ok(Literal(Number(-nr))),
+            (TokenType::Minus, Literal(Integer(nr))) => match nr.checked_neg() {
+                Some(nr) => Ok(Literal(Integer(nr))),
+                None => Err(Err::Interpreter(
+                    format!("Integer overflow for -{nr}."),
+                    self.line,
+                )),
+            },
+            (TokenType::Tilde, Literal(Integer(nr))) => Ok(Literal(Integer(!nr))),
             (TokenType::Exclamation, Literal(Boolean(istrue))) => Ok(Literal(Boolean(!istrue))),
             // !nil = true :
             (TokenType::Exclamation, Literal(Nil)) => Ok(Literal(Boolean(true))),
             (token, _) => Err(Err::Interpreter(
                 "NotImplementedUnaryExpr for :".to_string() + &token.to_string(),
-                69,
+                self.line,
             )),
         }
     }
@@ -188,30 +924,47 @@ impl Evaluates for UnaryExpr {
 
 impl Evaluates for BinaryExpr {
     fn evaluated(&self, env: Rc<Environment>) -> Result<Expr, Err> {
-        let left = (*self.left).evaluated(env.clone());
-        let right = (*self.right).evaluated(env);
+        let left = (*self.left).evaluated(env.clone())?;
+        let right = (*self.right).evaluated(env)?;
+        binary_operation(left, self.token.clone(), right, self.line)
+    }
+}
 
-        match (left?, self.token.clone(), right?) {
-            (left, TokenType::Minus, right) => subtraction(left, TokenType::Minus, right),
-            (left, TokenType::Slash, right) => division(left, TokenType::Slash, right),
-            (left, TokenType::Star, right) => multiplication(left, TokenType::Star, right),
-            (left, TokenType::Plus, right) => addition(left, TokenType::Plus, right),
-            (
-                left,
-                TokenType::Greater
-                | TokenType::GreaterEqual
-                | TokenType::Less
-                | TokenType::LessEqual,
-                right,
-            ) => comparison(left, self.token.clone(), right),
-            (left, TokenType::ExclamationEqual | TokenType::EqualEqual, right) => {
-                is_equal(left, self.token.clone(), right)
-            }
-            (left, token, right) => Err(Err::Interpreter(
-                format!("NotImplementedBinaryExpr for {left} {token} {right}."),
-                69,
-            )),
+/// applies the operator of a BinaryExpr (or of a compound assignment like '+=') to both values
+fn binary_operation(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
+    match (left, token.clone(), right) {
+        (left, TokenType::Minus, right) => subtraction(left, TokenType::Minus, right, line),
+        (left, TokenType::Slash, right) => division(left, TokenType::Slash, right, line),
+        (left, TokenType::Star, right) => multiplication(left, TokenType::Star, right, line),
+        (left, TokenType::Plus, right) => addition(left, TokenType::Plus, right, line),
+        (left, TokenType::Percent | TokenType::TildeSlash, right) => {
+            floored_division(left, token.clone(), right, line)
         }
+        (left, TokenType::StarStar, right) => {
+            exponentiation(left, TokenType::StarStar, right, line)
+        }
+        (
+            left,
+            TokenType::Ampersand
+            | TokenType::Pipe
+            | TokenType::Caret
+            | TokenType::LessLess
+            | TokenType::GreaterGreater,
+            right,
+        ) => bitwise(left, token.clone(), right, line),
+        (
+            left,
+            TokenType::Greater | TokenType::GreaterEqual | TokenType::Less | TokenType::LessEqual,
+            right,
+        ) => comparison(left, token.clone(), right, line),
+        (left, TokenType::ExclamationEqual | TokenType::EqualEqual, right) => {
+            is_equal(left, token.clone(), right, line)
+        }
+        (left, TokenType::In, right) => contains(left, right, line),
+        (left, token, right) => Err(Err::Interpreter(
+            format!("NotImplementedBinaryExpr for {left} {token} {right}."),
+            line,
+        )),
     }
 }
 
@@ -219,6 +972,12 @@ impl Evaluates for LogicalExpr {
     fn evaluated(&self, env: Rc<Environment>) -> Result<Expr, Err> {
         let left = self.left.evaluated(env.clone())?;
 
+        if self.token == TokenType::QuestionQuestion {
+            return match left {
+                Literal(Nil) => self.right.evaluated(env),
+                left => Ok(left),
+            };
+        }
         if self.token == TokenType::Or {
             if is_truthy(left.clone()) {
                 return Ok(Expr::Literal(Value::Boolean(true)));
@@ -246,58 +1005,186 @@ impl Evaluates for LogicalExpr {
             Helper Functions that handle some encapsulated logic
 */
 
+/// integers get promoted to floats, when they meet a float
+fn as_float(expr: &Expr) -> Option<f64> {
+    match expr {
+        Literal(Number(n)) => Some(*n),
+        Literal(Integer(n)) => Some(*n as f64),
+        _ => None,
+    }
+}
+
+/// integer math never wraps around silently, an overflow is a runtime error
+fn checked(
+    result: Option<i64>,
+    left: i64,
+    token: &TokenType,
+    right: i64,
+    line: usize,
+) -> Result<Expr, Err> {
+    match result {
+        Some(n) => Ok(Literal(Integer(n))),
+        None => Err(Err::Interpreter(
+            format!("Integer overflow for {left} {token} {right}."),
+            line,
+        )),
+    }
+}
+
 // helper function to evaluate BinaryExpr:
-fn subtraction(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
+fn subtraction(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
     match (left, token, right) {
-        (Literal(Number(l)), TokenType::Minus, Literal(Number(r))) => Ok(Literal(Number(l - r))),
-        (left, token, right) => Err(Err::Interpreter(
-            format!("FailedSubtraction for {left} {token} {right}."),
-            69,
-        )),
+        (Literal(Integer(l)), token, Literal(Integer(r))) => {
+            checked(l.checked_sub(r), l, &token, r, line)
+        }
+        (left, token, right) => match (as_float(&left), as_float(&right)) {
+            (Some(l), Some(r)) => Ok(Literal(Number(l - r))),
+            _ => Err(Err::Interpreter(
+                format!("FailedSubtraction for {left} {token} {right}."),
+                line,
+            )),
+        },
     }
 }
 
 // helper function to evaluate BinaryExpr:
-fn multiplication(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
+fn multiplication(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
     match (left, token, right) {
-        (Literal(Number(l)), TokenType::Star, Literal(Number(r))) => Ok(Literal(Number(l * r))),
-        (left, token, right) => Err(Err::Interpreter(
-            format!("FailedMultiplication for {left} {token} {right}."),
-            69,
+        (Literal(Integer(l)), token, Literal(Integer(r))) => {
+            checked(l.checked_mul(r), l, &token, r, line)
+        }
+        (left, token, right) => match (as_float(&left), as_float(&right)) {
+            (Some(l), Some(r)) => Ok(Literal(Number(l * r))),
+            _ => Err(Err::Interpreter(
+                format!("FailedMultiplication for {left} {token} {right}."),
+                line,
+            )),
+        },
+    }
+}
+
+// explicit checking for division by 0 errors:
+fn check_divisor(left: &Expr, token: &TokenType, right: &Expr, line: usize) -> Result<(), Err> {
+    if as_float(right) == Some(0.0) {
+        return Err(Err::Interpreter(
+            format!("FailedDivision for {left} {token} 0 (division by zero)"),
+            line,
+        ));
+    }
+    Ok(())
+}
+
+// helper function to evaluate BinaryExpr: '/' always results in a float, so 1/2 is 0.5
+fn division(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
+    check_divisor(&left, &token, &right, line)?;
+    match (as_float(&left), as_float(&right)) {
+        (Some(l), Some(r)) => Ok(Literal(Number(l / r))),
+        _ => Err(Err::Interpreter(
+            format!("FailedDivision for {left} {token} {right}"),
+            line,
         )),
     }
 }
 
-// helper function to evaluate BinaryExpr:
-fn division(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
-    // explicit checking for division by 0 errors:
-    if let Literal(Number(nr)) = right {
-        if nr == 0.0 || nr == -0.0 {
-            return Err(Err::Interpreter(
-                format!("FailedMultiplication for {left} / 0"),
-                69,
-            ));
+// helper function to evaluate BinaryExpr: 'a ~/ b' rounds down, 'a % b' is what remains of that
+// - so the remainder takes the sign of the divisor: -7 % 3 == 2 and (a ~/ b) * b + a % b == a
+fn floored_division(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
+    check_divisor(&left, &token, &right, line)?;
+    match (left, token, right) {
+        (Literal(Integer(l)), TokenType::TildeSlash, Literal(Integer(r))) => {
+            let quotient = l
+                .checked_div(r)
+                .map(|q| match l % r != 0 && (l < 0) != (r < 0) {
+                    true => q - 1,
+                    false => q,
+                });
+            checked(quotient, l, &TokenType::TildeSlash, r, line)
+        }
+        (Literal(Integer(l)), TokenType::Percent, Literal(Integer(r))) => {
+            let remainder = l.wrapping_rem(r);
+            match remainder != 0 && (remainder < 0) != (r < 0) {
+                true => Ok(Literal(Integer(remainder + r))),
+                false => Ok(Literal(Integer(remainder))),
+            }
+        }
+        (left, token, right) => match (as_float(&left), &token, as_float(&right)) {
+            (Some(l), TokenType::TildeSlash, Some(r)) => Ok(Literal(Number((l / r).floor()))),
+            (Some(l), TokenType::Percent, Some(r)) => Ok(Literal(Number(l - r * (l / r).floor()))),
+            _ => Err(Err::Interpreter(
+                format!("FailedDivision for {left} {token} {right}"),
+                line,
+            )),
+        },
+    }
+}
+
+// helper function to evaluate BinaryExpr: a negative exponent always results in a float
+fn exponentiation(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
+    match (left, token, right) {
+        (Literal(Integer(l)), token, Literal(Integer(r))) if r >= 0 => {
+            let power = u32::try_from(r).ok().and_then(|r| l.checked_pow(r));
+            checked(power, l, &token, r, line)
         }
+        (left, token, right) => match (as_float(&left), as_float(&right)) {
+            (Some(l), Some(r)) => Ok(Literal(Number(l.powf(r)))),
+            _ => Err(Err::Interpreter(
+                format!("FailedExponentiation for {left} {token} {right}."),
+                line,
+            )),
+        },
     }
+}
 
+// helper function to evaluate BinaryExpr: & | ^ << >> only work on integers
+fn bitwise(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
     match (left, token, right) {
-        (Literal(Number(l)), TokenType::Slash, Literal(Number(r))) => Ok(Literal(Number(l / r))),
+        (Literal(Integer(l)), TokenType::Ampersand, Literal(Integer(r))) => {
+            Ok(Literal(Integer(l & r)))
+        }
+        (Literal(Integer(l)), TokenType::Pipe, Literal(Integer(r))) => Ok(Literal(Integer(l | r))),
+        (Literal(Integer(l)), TokenType::Caret, Literal(Integer(r))) => Ok(Literal(Integer(l ^ r))),
+        (Literal(Integer(l)), token, Literal(Integer(r))) if !(0..64).contains(&r) => {
+            Err(Err::Interpreter(
+                format!("FailedShift for {l} {token} {r} (shift amount must be between 0 and 63)"),
+                line,
+            ))
+        }
+        (Literal(Integer(l)), TokenType::GreaterGreater, Literal(Integer(r))) => {
+            Ok(Literal(Integer(l >> r)))
+        }
+        (Literal(Integer(l)), TokenType::LessLess, Literal(Integer(r))) => {
+            // bits shifted out (or into the sign bit) are an overflow
+            let shifted = Some(l << r).filter(|shifted| shifted >> r == l);
+            checked(shifted, l, &TokenType::LessLess, r, line)
+        }
         (left, token, right) => Err(Err::Interpreter(
-            format!("FailedDivision for {left} {token} {right}"),
-            69,
+            format!("FailedBitwiseOperation for {left} {token} {right} (only works on integers)"),
+            line,
         )),
     }
 }
 
 // helper function to evaluate BinaryExpr:
-fn addition(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
+fn addition(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
     match (left, token, right) {
         // addition
+        (Literal(Integer(l)), token, Literal(Integer(r))) => {
+            checked(l.checked_add(r), l, &token, r, line)
+        }
         (Literal(Number(l)), TokenType::Plus, Literal(Number(r))) => Ok(Literal(Number(l + r))),
+        (Literal(Number(l)), TokenType::Plus, Literal(Integer(r))) => {
+            Ok(Literal(Number(l + r as f64)))
+        }
+        (Literal(Integer(l)), TokenType::Plus, Literal(Number(r))) => {
+            Ok(Literal(Number(l as f64 + r)))
+        }
         // string concatinations:
         (Literal(String(l)), TokenType::Plus, Literal(Number(r))) => {
             Ok(Literal(String(l + &r.to_string())))
         }
+        (Literal(String(l)), TokenType::Plus, Literal(Integer(r))) => {
+            Ok(Literal(String(l + &r.to_string())))
+        }
         (Literal(String(l)), TokenType::Plus, Literal(Boolean(r))) => {
             Ok(Literal(String(l + &r.to_string())))
         }
@@ -305,37 +1192,81 @@ fn addition(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
         (Literal(String(l)), TokenType::Plus, Literal(String(r))) => Ok(Literal(String(l + &r))),
         (left, token, right) => Err(Err::Interpreter(
             format!("FailedAddition for {left} {token} {right}"),
-            69,
+            line,
         )),
     }
 }
 
 // helper function to evaluate BinaryExpr:
-fn comparison(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
-    match (left, token, right) {
-        (Literal(Number(l)), TokenType::Less, Literal(Number(r))) => Ok(Literal(Boolean(l < r))),
-        (Literal(Number(l)), TokenType::LessEqual, Literal(Number(r))) => {
-            Ok(Literal(Boolean(l <= r)))
-        }
-        (Literal(Number(l)), TokenType::Greater, Literal(Number(r))) => Ok(Literal(Boolean(l > r))),
-        (Literal(Number(l)), TokenType::GreaterEqual, Literal(Number(r))) => {
-            Ok(Literal(Boolean(l >= r)))
+fn comparison(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
+    let ordering = match (&left, &right) {
+        (Literal(Integer(l)), Literal(Integer(r))) => l.partial_cmp(r),
+        _ => match (as_float(&left), as_float(&right)) {
+            (Some(l), Some(r)) => l.partial_cmp(&r),
+            _ => {
+                return Err(Err::Interpreter(
+                    format!("FailedComparison for {left} {token} {right}"),
+                    line,
+                ))
+            }
+        },
+    };
+    let result = match token {
+        TokenType::Less => ordering == Some(Ordering::Less),
+        TokenType::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        TokenType::Greater => ordering == Some(Ordering::Greater),
+        _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    };
+    Ok(Literal(Boolean(result)))
+}
+
+/// '==' on lox values. Integers and floats compare by value, also inside of lists and maps: [1] == [1.0]
+pub(crate) fn values_equal(left: &Expr, right: &Expr) -> bool {
+    let scalar_eq = |left: &Expr, right: &Expr| match (left, right) {
+        (Literal(Integer(l)), Literal(Number(r))) | (Literal(Number(r)), Literal(Integer(l))) => {
+            *l as f64 == *r
         }
-        (left, token, right) => Err(Err::Interpreter(
-            format!("FailedComparison for {left} {token} {right}"),
-            69,
-        )),
-    }
+        (left, right) => left == right,
+    };
+    deep_equal(left, right, scalar_eq, &mut vec![])
 }
 
 // helper function to evaluate BinaryExpr:
-fn is_equal(left: Expr, token: TokenType, right: Expr) -> Result<Expr, Err> {
+fn is_equal(left: Expr, token: TokenType, right: Expr, line: usize) -> Result<Expr, Err> {
     match (left, token, right) {
-        (l, TokenType::ExclamationEqual, r) => Ok(Literal(Boolean(l != r))),
-        (l, TokenType::EqualEqual, r) => Ok(Literal(Boolean(l == r))),
+        (l, TokenType::ExclamationEqual, r) => Ok(Literal(Boolean(!values_equal(&l, &r)))),
+        (l, TokenType::EqualEqual, r) => Ok(Literal(Boolean(values_equal(&l, &r)))),
         (left, token, right) => Err(Err::Interpreter(
             format!("FailedEqualityCheck for {left} {token} {right}"),
-            69,
+            line,
+        )),
+    }
+}
+
+// helper function to evaluate BinaryExpr: ('key' in map) or (element in list)
+fn contains(left: Expr, right: Expr, line: usize) -> Result<Expr, Err> {
+    match right {
+        Literal(Value::List(list)) => {
+            let found = list
+                .borrow()
+                .iter()
+                .any(|element| values_equal(element, &left));
+            Ok(Literal(Boolean(found)))
+        }
+        Literal(Value::Range(range)) => match left {
+            Literal(Integer(n)) => Ok(Literal(Boolean(range.contains(n)))),
+            Literal(Number(n)) if n.fract() == 0.0 => {
+                Ok(Literal(Boolean(range.contains(n as i64))))
+            }
+            _ => Ok(Literal(Boolean(false))),
+        },
+        Literal(Value::Map(map)) => match MapKey::new(&left) {
+            Some(key) => Ok(Literal(Boolean(map.borrow().contains(&key)))),
+            None => Ok(Literal(Boolean(false))), // unhashable values can never be a key
+        },
+        right => Err(Err::Interpreter(
+            format!("FailedContains for {left} in {right}"),
+            line,
         )),
     }
 }
@@ -423,4 +1354,80 @@ mod tests {
         test("-1.2*0.2;", Literal(Number(-0.24)));
         // test("-1.2*nil;", RuntimeErr(FailedMultiplication));
     }
+
+    #[test]
+    fn modulo_and_floor_division() {
+        test("7 % 3;", Literal(Integer(1)));
+        test("-7 % 3;", Literal(Integer(2)));
+        test("7 % -3;", Literal(Integer(-2)));
+        test("7.5 % 2;", Literal(Number(1.5)));
+        test("7 ~/ 2;", Literal(Integer(3)));
+        test("-7 ~/ 2;", Literal(Integer(-4)));
+        test("-7.0 ~/ 2;", Literal(Number(-4.0)));
+        test("1 + 7 % 4 * 2;", Literal(Integer(7)));
+    }
+
+    #[test]
+    fn exponentiation() {
+        test("2 ** 10;", Literal(Integer(1024)));
+        test("2 ** 3 ** 2;", Literal(Integer(512)));
+        test("-2 ** 2;", Literal(Integer(-4)));
+        test("2 ** -1;", Literal(Number(0.5)));
+        test("3 * 2 ** 2;", Literal(Integer(12)));
+        test("4 ** 0.5;", Literal(Number(2.0)));
+    }
+
+    #[test]
+    fn integers() {
+        test("1 + 2;", Literal(Integer(3)));
+        test("1 + 0.5;", Literal(Number(1.5)));
+        test("1 / 2;", Literal(Number(0.5)));
+        test("1 == 1.0;", Literal(Boolean(true)));
+        test("2 > 1.5;", Literal(Boolean(true)));
+        test("9223372036854775807 - 1;", Literal(Integer(9223372036854775806)));
+        test("0xFF;", Literal(Integer(255)));
+        test("0b1010;", Literal(Integer(10)));
+    }
+
+    #[test]
+    fn conditional_and_nil_coalescing() {
+        test("true ? 1 : 2;", Literal(Integer(1)));
+        test("1 > 2 ? 1 : 2 > 1 ? 2 : 3;", Literal(Integer(2)));
+        test("nil ?? 2;", Literal(Integer(2)));
+        test("false ?? 2;", Literal(Boolean(false)));
+    }
+
+    #[test]
+    fn bitwise() {
+        test("0b1100 & 0b1010;", Literal(Integer(8)));
+        test("0b1100 | 0b1010;", Literal(Integer(14)));
+        test("0b1100 ^ 0b1010;", Literal(Integer(6)));
+        test("~0;", Literal(Integer(-1)));
+        test("1 << 4;", Literal(Integer(16)));
+        test("-16 >> 2;", Literal(Integer(-4)));
+        // shifts bind tighter than &, which binds tighter than the comparison:
+        test("1 << 2 & 4 == 4;", Literal(Boolean(true)));
+    }
+
+    #[test]
+    fn self_referencing_collections_are_equal() {
+        // [[...]] twice, each list holding itself
+        let cyclic_list = || {
+            let list = Rc::new(RefCell::new(vec![Literal(Nil)]));
+            list.borrow_mut()[0] = Literal(Value::List(Rc::clone(&list)));
+            Value::List(list)
+        };
+        assert_eq!(cyclic_list(), cyclic_list());
+
+        let cyclic_map = |x: i64| {
+            let map = Rc::new(RefCell::new(OrderedMap::default()));
+            map.borrow_mut()
+                .insert(MapKey::String("x".into()), Literal(Integer(x)));
+            let own = Literal(Value::Map(Rc::clone(&map)));
+            map.borrow_mut().insert(MapKey::String("self".into()), own);
+            Value::Map(map)
+        };
+        assert_eq!(cyclic_map(1), cyclic_map(1));
+        assert_ne!(cyclic_map(1), cyclic_map(2));
+    }
 }
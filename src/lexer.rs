@@ -13,24 +13,46 @@ use crate::types::{Err, Token, TokenType, TokenType::*};
 pub struct Scanner<'a> {
     source: &'a str,
     source_arr: Vec<char>, // TODO remove?
+    /// byte offset of each char in source, start and current count chars not bytes
+    byte_offsets: Vec<usize>,
     //iterator: CharIndices<'a>,    // iterator over all chars.
     tokens: Vec<Token<'a>>, // TODO: change to linkedList, maybe?
     errors: Vec<Err>,
     start: usize,
     current: usize,
     line: usize,
+    /// strings we are inside of, while scanning their embedded ${expression}
+    open_strings: Vec<OpenString>,
+}
+
+/// A string literal the scanner is in the middle of. For "a ${x} b" the scanner leaves the string at
+/// the '${', scans the tokens of the expression and picks the string back up at the matching '}'
+#[derive(Debug)]
+struct OpenString {
+    raw: bool,
+    multi_line: bool,
+    /// '{' opened (and not yet closed) inside the current ${expression}
+    brace_depth: usize,
+    /// for each part of the string: the index of its token, its unprocessed text and its first line
+    parts: Vec<(usize, std::string::String, usize)>,
 }
 
 pub fn new_scanner(source: &str) -> Scanner {
     let mut s = Scanner {
         source: source,
         source_arr: source.chars().collect(),
+        byte_offsets: source
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(source.len()))
+            .collect(),
         //iterator: source.char_indices(),
         tokens: vec![],
         errors: vec![],
         start: 0,   // offsets that index into the string
         current: 0, // offsets that index into the string
         line: 1,
+        open_strings: vec![],
     };
     s.scan_all_tokens();
     s
@@ -49,6 +71,11 @@ impl<'a> Scanner<'a> {
             self.start = self.current;
             self.scan_token();
         }
+        if let Some(string) = self.open_strings.first() {
+            let line = string.parts[0].2;
+            self.errors
+                .push(Err::Lexer("Unterminated string".to_string(), line));
+        }
         self.tokens.push(Token {
             typ: EOF,
             lexeme: "",
@@ -57,7 +84,7 @@ impl<'a> Scanner<'a> {
     }
 
     fn is_at_end(&self) -> bool {
-        self.current >= self.source.len()
+        self.current >= self.source_arr.len()
     }
 
     fn scan_token(&mut self) {
@@ -66,46 +93,141 @@ impl<'a> Scanner<'a> {
             // 1 Char long
             '(' => self.add_token(OpenParen),
             ')' => self.add_token(CloseParen),
-            '{' => self.add_token(OpenBrace),
-            '}' => self.add_token(CloseBrace),
+            '{' => {
+                if let Some(string) = self.open_strings.last_mut() {
+                    string.brace_depth += 1;
+                }
+                self.add_token(OpenBrace)
+            }
+            '}' => match self.open_strings.pop() {
+                // the '}' that closes an embedded ${expression} continues its string
+                Some(string) if string.brace_depth == 0 => self.string_part(string),
+                Some(mut string) => {
+                    string.brace_depth -= 1;
+                    self.open_strings.push(string);
+                    self.add_token(CloseBrace)
+                }
+                None => self.add_token(CloseBrace),
+            },
+            '[' => self.add_token(OpenBracket),
+            ']' => self.add_token(CloseBracket),
             ',' => self.add_token(Comma),
-            '.' => self.add_token(Dot),
-            '-' => self.add_token(Minus),
-            '+' => self.add_token(Plus),
+            // ranges '0..10' and '0..=10', rest and spread '...xs'.
+            // (number literals only take a '.' followed by a digit)
+            '.' => {
+                if !self.check_for('.') {
+                    self.add_token(Dot);
+                } else if self.check_for('.') {
+                    self.add_token(DotDotDot);
+                } else if self.check_for('=') {
+                    self.add_token(DotDotEqual);
+                } else {
+                    self.add_token(DotDot);
+                }
+            }
             ';' => self.add_token(Semicolon),
-            '*' => self.add_token(Star),
+            '%' => self.add_token(Percent),
+            '&' => self.add_token(Ampersand),
+            '|' => self.add_token(Pipe),
+            '^' => self.add_token(Caret),
+            ':' => self.add_token(Colon),
+            '?' => match self.check_for('?') {
+                true => self.add_token(QuestionQuestion),
+                false => self.add_token(Question),
+            },
             // 1-2 char long combinations:
+            '-' => {
+                if self.check_for('-') {
+                    self.add_token(MinusMinus);
+                } else if self.check_for('=') {
+                    self.add_token(MinusEqual);
+                } else {
+                    self.add_token(Minus);
+                }
+            }
+            '+' => {
+                if self.check_for('+') {
+                    self.add_token(PlusPlus);
+                } else if self.check_for('=') {
+                    self.add_token(PlusEqual);
+                } else {
+                    self.add_token(Plus);
+                }
+            }
+            '*' => {
+                if self.check_for('*') {
+                    self.add_token(StarStar);
+                } else if self.check_for('=') {
+                    self.add_token(StarEqual);
+                } else {
+                    self.add_token(Star);
+                }
+            }
+            // floor division, since '//' already starts a comment
+            '~' => match self.check_for('/') {
+                true => self.add_token(TildeSlash),
+                false => self.add_token(Tilde),
+            },
             '!' => match self.check_for('=') {
                 true => self.add_token(ExclamationEqual),
                 false => self.add_token(Exclamation),
             },
-            '=' => match self.check_for('=') {
-                true => self.add_token(EqualEqual),
-                false => self.add_token(Equal),
-            },
-            '<' => match self.check_for('=') {
-                true => self.add_token(LessEqual),
-                false => self.add_token(Less),
-            },
-            '>' => match self.check_for('=') {
-                true => self.add_token(GreaterEqual),
-                false => self.add_token(Greater),
-            },
-            '/' => match self.check_for('/') {
-                true => self.skip_line(),
-                false => self.add_token(Slash),
-            },
+            '=' => {
+                if self.check_for('=') {
+                    self.add_token(EqualEqual);
+                } else if self.check_for('>') {
+                    self.add_token(FatArrow);
+                } else {
+                    self.add_token(Equal);
+                }
+            }
+            '<' => {
+                if self.check_for('=') {
+                    self.add_token(LessEqual);
+                } else if self.check_for('<') {
+                    self.add_token(LessLess);
+                } else {
+                    self.add_token(Less);
+                }
+            }
+            '>' => {
+                if self.check_for('=') {
+                    self.add_token(GreaterEqual);
+                } else if self.check_for('>') {
+                    self.add_token(GreaterGreater);
+                } else {
+                    self.add_token(Greater);
+                }
+            }
+            '/' => {
+                if self.check_for('/') {
+                    self.skip_line();
+                } else if self.check_for('*') {
+                    self.skip_block_comment();
+                } else if self.check_for('=') {
+                    self.add_token(SlashEqual);
+                } else {
+                    self.add_token(Slash);
+                }
+            }
             // ignore whitespaces
             ' ' => {}
             '\r' => {}
             '\t' => {}
             '\n' => self.line += 1,
             // literals:
-            '"' => self.string_literal(),
+            '"' => self.string_literal(false),
             _ => {
                 if c.is_digit(10) {
                     // digit -> numbers
                     self.number_literal();
+                } else if c == 'r' && self.peek() == '"' {
+                    // raw string r"C:\path" -> no escape sequences
+                    self.advance_char();
+                    self.string_literal(true);
+                } else if c == '_' && !(self.peek().is_alphanumeric() || self.peek() == '_') {
+                    // a lone '_' is the wildcard pattern, names still can't start with it
+                    self.add_token(Underscore);
                 } else if c.is_alphabetic() {
                     // word -> Identifier || Reserved-Word
                     self.identifier_literal();
@@ -124,7 +246,7 @@ impl<'a> Scanner<'a> {
     }
 
     fn add_token(&mut self, token: TokenType) {
-        let lexeme = &self.source[self.start..self.current];
+        let lexeme = self.lexeme();
         self.tokens.push(Token {
             typ: token,
             lexeme,
@@ -132,6 +254,11 @@ impl<'a> Scanner<'a> {
         });
     }
 
+    // the source text of the current lexeme
+    fn lexeme(&self) -> &'a str {
+        &self.source[self.byte_offsets[self.start]..self.byte_offsets[self.current]]
+    }
+
     // to check for 1-2 char long combinations. Ex: ! vs !=, < vs <=...
     fn check_for(&mut self, expected: char) -> bool {
         if self.is_at_end() || (self.source_arr[self.current] != expected) {
@@ -150,12 +277,19 @@ impl<'a> Scanner<'a> {
     }
 
     fn peek_two(&self) -> char {
-        if self.current + 1 >= self.source.len() {
+        if self.current + 1 >= self.source_arr.len() {
             return '\0'; // return EOF
         }
         self.source_arr[self.current + 1]
     }
 
+    fn peek_three(&self) -> char {
+        if self.current + 2 >= self.source_arr.len() {
+            return '\0'; // return EOF
+        }
+        self.source_arr[self.current + 2]
+    }
+
     // skip line fully (after // comment)
     fn skip_line(&mut self) {
         while self.peek() != '\n' && !self.is_at_end() {
@@ -163,26 +297,200 @@ impl<'a> Scanner<'a> {
         }
     }
 
-    // consume characters untill we hit the closing "
-    fn string_literal(&mut self) {
-        while self.peek() != '"' && !self.is_at_end() {
-            if self.peek() == '\n' {
-                self.line += 1;
+    // skip a /* block comment */ they can be nested: /* outer /* inner */ still outer */
+    fn skip_block_comment(&mut self) {
+        let opened_at = self.line;
+        let mut depth = 1;
+        while depth > 0 {
+            if self.is_at_end() {
+                self.errors.push(Err::Lexer(
+                    "Unterminated block comment".to_string(),
+                    opened_at,
+                ));
+                return;
             }
+            match self.advance_char() {
+                '\n' => self.line += 1,
+                '/' if self.check_for('*') => depth += 1,
+                '*' if self.check_for('/') => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+
+    // a string literal starts, r"raw" or """multi-line""" strings are possible as well
+    fn string_literal(&mut self, raw: bool) {
+        let multi_line = self.peek() == '"' && self.peek_two() == '"';
+        if multi_line {
+            self.advance_char();
             self.advance_char();
         }
-        if self.is_at_end() {
-            self.errors
-                .push(Err::Lexer("Unterminated string".to_string(), self.line));
+        self.string_part(OpenString {
+            raw,
+            multi_line,
+            brace_depth: 0,
+            parts: vec![],
+        });
+    }
+
+    // consume characters untill we hit the closing " (or """) or an embedded ${expression}
+    fn string_part(&mut self, mut string: OpenString) {
+        let first_line = self.line;
+        let content_start = self.current;
+        let mut interpolation = false;
+        loop {
+            if self.is_at_end() {
+                self.errors
+                    .push(Err::Lexer("Unterminated string".to_string(), self.line));
+                return;
+            }
+            match self.peek() {
+                '"' if !string.multi_line => break,
+                '"' if self.peek_two() == '"' && self.peek_three() == '"' => break,
+                '$' if !string.raw && self.peek_two() == '{' => {
+                    interpolation = true;
+                    break;
+                }
+                // skip the escaped char, so \" does not end the string
+                '\\' if !string.raw && self.peek_two() != '\n' && self.peek_two() != '\0' => {
+                    self.advance_char();
+                }
+                '\n' => self.line += 1,
+                _ => {}
+            }
+            self.advance_char();
+        }
+        let content: std::string::String = self.source_arr[content_start..self.current]
+            .iter()
+            .collect();
+        string.parts.push((self.tokens.len(), content, first_line));
+
+        if interpolation {
+            // consume the ${ then the tokens of the expression get scanned as usual
+            self.advance_char();
+            self.advance_char();
+            self.add_token(Interpolation(std::string::String::new()));
+            self.open_strings.push(string);
             return;
         }
-        self.advance_char(); // consume the closing "
-        let string_value = &self.source[self.start + 1..self.current - 1]; // TODO: do those values line up?
-        self.add_token(String(string_value.to_string()));
+        // consume the closing " or """
+        for _ in 0..if string.multi_line { 3 } else { 1 } {
+            self.advance_char();
+        }
+        self.add_token(String(std::string::String::new()));
+        self.finish_string(string);
+    }
+
+    // once all parts of a string are scanned we can strip the indentation and replace escape sequences
+    fn finish_string(&mut self, string: OpenString) {
+        let mut texts: Vec<std::string::String> = string
+            .parts
+            .iter()
+            .map(|(_, text, _)| text.clone())
+            .collect();
+        if string.multi_line {
+            // a \0 stands in for each ${expression}, so a line starting with one is not blank
+            texts = strip_indentation(&texts.join("\0"))
+                .split('\0')
+                .map(|text| text.to_string())
+                .collect();
+        }
+        for (i, (token_index, raw_text, mut line)) in string.parts.into_iter().enumerate() {
+            if i == 0 && string.multi_line && raw_text.starts_with('\n') {
+                line += 1;
+            }
+            let mut text = std::mem::take(&mut texts[i]);
+            if !string.raw {
+                text = self.unescape(&text, line);
+            }
+            match &mut self.tokens[token_index].typ {
+                Interpolation(part) | String(part) => *part = text,
+                _ => {}
+            }
+        }
+    }
+
+    /// replaces escape sequences like \n or \u{1F600} with the chars they stand for
+    /// - invalid escapes are reported and left in the string as they are
+    fn unescape(&mut self, text: &str, mut line: usize) -> std::string::String {
+        let mut result = std::string::String::with_capacity(text.len());
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == '\n' {
+                line += 1;
+            }
+            if c != '\\' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => result.push('\n'),
+                Some('t') => result.push('\t'),
+                Some('r') => result.push('\r'),
+                Some('0') => result.push('\0'),
+                Some('"') => result.push('"'),
+                Some('\\') => result.push('\\'),
+                Some('$') => result.push('$'),
+                Some('u') => {
+                    let mut hex = std::string::String::new();
+                    let mut closed = false;
+                    if chars.peek() == Some(&'{') {
+                        chars.next();
+                        while let Some(&h) = chars.peek() {
+                            chars.next();
+                            if h == '}' {
+                                closed = true;
+                                break;
+                            }
+                            hex.push(h);
+                        }
+                    }
+                    let unicode = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32);
+                    match unicode {
+                        Some(ch) if closed && hex.len() <= 6 => result.push(ch),
+                        _ => {
+                            self.errors.push(Err::Lexer(
+                                format!("Invalid unicode escape '\\u{{{hex}}}' in string"),
+                                line,
+                            ));
+                        }
+                    }
+                }
+                Some(other) => {
+                    if other == '\n' {
+                        line += 1;
+                    }
+                    self.errors.push(Err::Lexer(
+                        format!("Invalid escape sequence '\\{other}' in string"),
+                        line,
+                    ));
+                    result.push('\\');
+                    result.push(other);
+                }
+                None => result.push('\\'),
+            }
+        }
+        result
     }
 
     // consume characters formatted aaa.bb untill no more digits found (with one possible .)
+    // - without a fraction part it becomes an integer. 0xFF and 0b1010 are integers as well
     fn number_literal(&mut self) {
+        if self.source_arr[self.start] == '0' && (self.peek() == 'x' || self.peek() == 'b') {
+            let radix = if self.advance_char() == 'x' { 16 } else { 2 };
+            while self.peek().is_digit(radix) {
+                self.advance_char();
+            }
+            let s = self.lexeme();
+            match i64::from_str_radix(&s[2..], radix) {
+                Ok(number) => self.add_token(Integer(number)),
+                Err(_) => self.errors.push(Err::Lexer(
+                    format!("Invalid integer literal '{s}'"),
+                    self.line,
+                )),
+            }
+            return;
+        }
         while self.peek().is_digit(10) {
             self.advance_char();
         }
@@ -193,8 +501,18 @@ impl<'a> Scanner<'a> {
             while self.peek().is_digit(10) {
                 self.advance_char(); // consume digits after . 12.xx
             }
+        } else {
+            let s = self.lexeme();
+            match s.parse::<i64>() {
+                Ok(number) => self.add_token(Integer(number)),
+                Err(_) => self.errors.push(Err::Lexer(
+                    format!("Integer literal '{s}' does not fit into 64 bits"),
+                    self.line,
+                )),
+            }
+            return;
         }
-        let s = &self.source[self.start..self.current];
+        let s = self.lexeme();
         let number = s.parse::<f64>().unwrap_or_else(|_| {
             self.errors.push(Err::Lexer(
                 "Failed to Parse Number->Float, used default value 0.0 instead!".to_string(),
@@ -210,7 +528,7 @@ impl<'a> Scanner<'a> {
         while self.peek().is_alphanumeric() || self.peek() == '_' {
             self.advance_char();
         }
-        let text = &self.source[self.start..self.current];
+        let text = self.lexeme();
         match KEYWORDS.get(text) {
             Some(token_type) => self.add_token(token_type.clone()), // isKeyword    like "return"
             None => self.add_token(Identifier),                     // isIdentifier like "some_var"
@@ -218,24 +536,59 @@ impl<'a> Scanner<'a> {
     }
 }
 
+/// The text of a """multi-line string""" gets its common indentation removed:
+/// - a line break right after the opening quotes is dropped
+/// - so is the last line, if it only holds the indentation of the closing quotes
+fn strip_indentation(text: &str) -> std::string::String {
+    let text = text.strip_prefix('\n').unwrap_or(text);
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    if lines.len() > 1 && lines.last().is_some_and(|l| l.trim().is_empty()) {
+        lines.pop();
+    }
+    let indent = lines
+        .iter()
+        .filter(|l| !l.trim().is_empty())
+        .map(|l| l.chars().take_while(|c| *c == ' ' || *c == '\t').count())
+        .min()
+        .unwrap_or(0);
+    lines
+        .iter()
+        .map(|l| l.chars().skip(indent).collect::<std::string::String>())
+        .collect::<Vec<std::string::String>>()
+        .join("\n")
+}
+
 // static KEYWORDS hashmap of reserved keywords and mapping them to the enums
 lazy_static! {
     static ref KEYWORDS: HashMap<&'static str, TokenType> = {
         let mut map = HashMap::new();
         map.insert("and", And);
+        map.insert("as", As);
+        map.insert("break", Break);
+        map.insert("catch", Catch);
         map.insert("class", Class);
+        map.insert("const", Const);
+        map.insert("continue", Continue);
+        map.insert("delete", Delete);
         map.insert("else", Else);
+        map.insert("enum", Enum);
         map.insert("false", False);
+        map.insert("finally", Finally);
         map.insert("for", For);
         map.insert("fun", Fun);
         map.insert("if", If);
+        map.insert("import", Import);
+        map.insert("in", In);
+        map.insert("match", Match);
         map.insert("nil", Nil);
         map.insert("or", Or);
         map.insert("print", Print);
         map.insert("return", Return);
         map.insert("super", Super);
         map.insert("this", This);
+        map.insert("throw", Throw);
         map.insert("true", True);
+        map.insert("try", Try);
         map.insert("var", Var);
         map.insert("while", While);
         map
@@ -293,11 +646,24 @@ mod tests {
         );
     }
 
+    #[test]
+    fn brackets() {
+        _is_expected(
+            "xs[0]",
+            vec![
+                ("xs", Identifier),
+                ("[", OpenBracket),
+                ("0", Integer(0)),
+                ("]", CloseBracket),
+            ],
+        );
+    }
+
     #[test]
     fn two_char_longs() {
         _is_expected(
             "1=2",
-            vec![("1", Number(1.0)), ("=", Equal), ("2", Number(2.0))],
+            vec![("1", Integer(1)), ("=", Equal), ("2", Integer(2))],
         );
         _is_expected(
             "true== false",
@@ -307,7 +673,7 @@ mod tests {
             "-05 !=00232.5",
             vec![
                 ("-", Minus),
-                ("05", Number(5.0)),
+                ("05", Integer(5)),
                 ("!=", ExclamationEqual),
                 ("00232.5", Number(232.5)),
             ],
@@ -325,9 +691,9 @@ mod tests {
             "return 1 / 2 // somecomment is NOT token/ // all ignored",
             vec![
                 ("return", Return),
-                ("1", Number(1.0)),
+                ("1", Integer(1)),
                 ("/", Slash),
-                ("2", Number(2.0)),
+                ("2", Integer(2)),
             ],
         );
     }
@@ -345,8 +711,24 @@ mod tests {
                 ("this", This),
                 (".", Dot),
                 ("thisname", Identifier),
-                ("+", Plus),
-                ("+", Plus),
+                ("++", PlusPlus),
+            ],
+        );
+    }
+
+    #[test]
+    fn ranges_next_to_numbers() {
+        _is_expected(
+            "1..5 1.5..=x.y",
+            vec![
+                ("1", Integer(1)),
+                ("..", DotDot),
+                ("5", Integer(5)),
+                ("1.5", Number(1.5)),
+                ("..=", DotDotEqual),
+                ("x", Identifier),
+                (".", Dot),
+                ("y", Identifier),
             ],
         );
     }
@@ -405,8 +787,79 @@ mod tests {
     }
     #[test]
     fn unexpected_character_error() {
-        let s = new_scanner("#? v_a_r_ok _varNotOk");
+        let s = new_scanner("#@ v_a_r_ok _varNotOk");
         let (_tokens, errs) = s.results();
         assert_eq!(3, errs.len());
     }
+
+    #[test]
+    fn string_escapes() {
+        let s = new_scanner(r#""a\tb\n\"q\" \\ \u{1F600}""#);
+        let (tokens, errs) = s.results();
+        assert!(errs.is_empty());
+        assert_eq!(tokens[0].typ, String("a\tb\n\"q\" \\ \u{1F600}".into()));
+
+        let s = new_scanner(r#""bad \q and \u{110000}""#);
+        let (_tokens, errs) = s.results();
+        assert_eq!(2, errs.len());
+    }
+
+    #[test]
+    fn raw_and_multi_line_strings() {
+        let s = new_scanner(r#"r"C:\new\table""#);
+        let (tokens, errs) = s.results();
+        assert!(errs.is_empty());
+        assert_eq!(tokens[0].typ, String(r"C:\new\table".into()));
+
+        let s = new_scanner("\"\"\"\n    total:\n      12\n    \"\"\"");
+        let (tokens, errs) = s.results();
+        assert!(errs.is_empty());
+        assert_eq!(tokens[0].typ, String("total:\n  12".into()));
+    }
+
+    #[test]
+    fn block_comments() {
+        _is_expected(
+            "1 /* a /* nested */ comment */ ;",
+            vec![("1", Integer(1)), (";", Semicolon)],
+        );
+
+        let s = new_scanner("/* line one\n line two */\nx");
+        let (tokens, errs) = s.results();
+        assert!(errs.is_empty());
+        assert_eq!(tokens[0].line, 3);
+
+        let s = new_scanner("\n/* opened /* nested */\n never closed");
+        let (_tokens, errs) = s.results();
+        assert_eq!(
+            errs,
+            vec![Err::Lexer("Unterminated block comment".into(), 2)]
+        );
+    }
+
+    #[test]
+    fn string_interpolation() {
+        let s = new_scanner(r#""a ${x} b ${{"k": 1}["k"]}""#);
+        let (tokens, errs) = s.results();
+        assert!(errs.is_empty());
+        let types: Vec<TokenType> = tokens.iter().map(|t| t.typ.clone()).collect();
+        assert_eq!(
+            types,
+            vec![
+                Interpolation("a ".into()),
+                Identifier,
+                Interpolation(" b ".into()),
+                OpenBrace,
+                String("k".into()),
+                Colon,
+                Integer(1),
+                CloseBrace,
+                OpenBracket,
+                String("k".into()),
+                CloseBracket,
+                String("".into()),
+                EOF,
+            ]
+        );
+    }
 }
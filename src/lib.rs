@@ -2,19 +2,103 @@
     The Tree-Walk Interpreter jlox
 */
 
-use std::io::{self, Write};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    rc::Rc,
+};
 
-use crate::{lexer::new_scanner, parser::AST};
+use crate::{environment::Environment, lexer::new_scanner, modules::Modules, parser::AST};
 mod environment;
 mod expressions;
 mod interpreter;
 mod lexer;
+mod modules;
 mod parser;
 mod statements;
 mod types;
 
+pub use expressions::{Arity, Value};
+pub use types::Err;
+
+/// Holds the global scope that survives between runs.
+/// - register native functions on it before running a script
+pub struct Interpreter {
+    global_scope: Rc<Environment>,
+    /// every interpreter loads its own copy of a module, on top of its own natives
+    modules: Rc<Modules>,
+}
+
+impl Interpreter {
+    /// a fresh global scope on top of the builtin natives like 'clock()'
+    /// - imported modules share the builtins, but not the global scope
+    pub fn new() -> Self {
+        let modules = Rc::new(Modules::default());
+        let builtins = interpreter::build_global_scope(Rc::clone(&modules));
+        Interpreter {
+            global_scope: Rc::new(Environment::new(Some(builtins))),
+            modules,
+        }
+    }
+
+    /// makes a Rust closure callable from lox under 'name'
+    /// - arguments get checked against the arity before the closure gets called
+    /// - returning Err(message) raises a runtime error in the line of the call
+    pub fn register_native<F>(&mut self, name: &str, arity: Arity, func: F)
+    where
+        F: Fn(&[Value]) -> Result<Value, String> + 'static,
+    {
+        interpreter::define_native(&self.global_scope.root(), name, arity, Rc::new(func));
+    }
+
+    /// runs the source on top of the global scope. Syntax errors get printed right away.
+    /// - an error nobody caught stops the run and gets returned, the global scope stays usable
+    pub fn run(&self, input: String, print_ast: bool) -> Result<(), Err> {
+        let lexer = new_scanner(&input);
+        let (tokens, mut errors) = lexer.results();
+
+        let ast = AST::new(tokens);
+
+        //optional debug info (prints the ast prefore interpreting it)
+        if print_ast {
+            println!("AST: {}", ast.print());
+        }
+        let expr = ast.root;
+
+        // join errors together and print them out:
+        errors.extend(ast.errors);
+        for er in errors {
+            println!("{}", er.to_string());
+        }
+        interpreter::interpret(expr, self.global_scope.clone())
+    }
+
+    /// runs the file at path. Imports inside of it get resolved relative to its directory.
+    pub fn run_file(&self, path: &Path, print_ast: bool) -> Result<(), Err> {
+        let input = fs::read_to_string(path)
+            .map_err(|e| Err::Interpreter(format!("Can't read '{}': {e}.", path.display()), 0))?;
+        // a native called from a running file could try to run that same file again
+        if !self.modules.enter_file(path) {
+            let path = path.display();
+            return Err(Err::Interpreter(format!("Circular import of '{path}'."), 0));
+        }
+        let result = self.run(input, print_ast);
+        self.modules.leave_file();
+        result
+    }
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 pub fn run_prompt() {
     println!("Interpreter running, input a line:");
+    // one interpreter for the whole session, so variables survive from one line to the next
+    let interpreter = Interpreter::new();
     loop {
         print!(">");
         io::stdout().flush().expect("flush failed!");
@@ -25,30 +109,19 @@ pub fn run_prompt() {
         if input.starts_with("#exit") {
             break;
         }
-        run(input, true);
+        // an uncaught error only ends that line, the session goes on
+        if let Err(e) = interpreter.run(input, true) {
+            println!("{e}");
+        }
     }
 }
 
-pub fn run_file(input: String, print_ast: bool) {
-    run(input, print_ast);
+/// runs the source code of a file in a fresh interpreter. Imports get resolved relative to the working directory.
+pub fn run_file(input: String, print_ast: bool) -> Result<(), Err> {
+    Interpreter::new().run(input, print_ast)
 }
 
-fn run(input: String, print_ast: bool) {
-    let lexer = new_scanner(&input);
-    let (tokens, mut errors) = lexer.results();
-
-    let ast = AST::new(tokens);
-
-    //optional debug info (prints the ast prefore interpreting it)
-    if print_ast {
-        println!("AST: {}", ast.print());
-    }
-    let expr = ast.root;
-
-    // join errors together and print them out:
-    errors.extend(ast.errors);
-    for er in errors {
-        println!("{}", er.to_string());
-    }
-    interpreter::interpret(expr);
+/// reads and runs the file at path in a fresh interpreter. Imports get resolved relative to its directory.
+pub fn run_path(path: &str, print_ast: bool) -> Result<(), Err> {
+    Interpreter::new().run_file(Path::new(path), print_ast)
 }
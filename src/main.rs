@@ -1,4 +1,4 @@
-use std::{env, fs, process};
+use std::{env, process};
 
 fn main() {
     let args: Vec<String> = env::args().collect();
@@ -7,16 +7,14 @@ fn main() {
         rs_interpreter::run_prompt(); // this always runs in debug mode
     } else if args.len() == 2 {
         // running a file from path
-        if let Some(source_code) = open_file(&args[1]) {
-            rs_interpreter::run_file(source_code, false);
-        } else {
+        if let Err(e) = rs_interpreter::run_path(&args[1], false) {
+            println!("{e}");
             process::exit(1);
         }
     } else if args.len() == 3 && args[2] == "--debug" {
         // debug flag to print out ast
-        if let Some(source_code) = open_file(&args[1]) {
-            rs_interpreter::run_file(source_code, true);
-        } else {
+        if let Err(e) = rs_interpreter::run_path(&args[1], true) {
+            println!("{e}");
             process::exit(1);
         }
     } else {
@@ -24,7 +22,3 @@ fn main() {
         process::exit(1);
     }
 }
-
-fn open_file(path: &str) -> Option<String> {
-    fs::read_to_string(path).ok()
-}
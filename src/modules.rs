@@ -0,0 +1,123 @@
+/*
+    Modules: 'import "path/to/file.lox" as name;'
+        - paths are resolved relative to the directory of the file that does the import
+        - every file only runs once, importing it again hands out the same namespace
+        - a file that (indirectly) imports itself is reported as circular import
+*/
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    environment::Environment, expressions::Module, lexer::new_scanner, parser::AST, types::Err,
+};
+
+/// The modules of one interpreter. The builtin scope holds on to it, so imports can reach it.
+#[derive(Debug, Default)]
+pub struct Modules {
+    /// every module that finished loading, by its canonical path
+    loaded: RefCell<HashMap<PathBuf, Rc<Module>>>,
+    /// the files currently running, the last one is doing the import right now
+    loading: RefCell<Vec<PathBuf>>,
+}
+
+impl Modules {
+    /// marks a file as currently running, so imports inside of it resolve relative to it
+    /// - returns false if the file is already running (a circular import)
+    pub fn enter_file(&self, path: &Path) -> bool {
+        let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let mut loading = self.loading.borrow_mut();
+        if loading.contains(&path) {
+            return false;
+        }
+        loading.push(path);
+        true
+    }
+
+    pub fn leave_file(&self) {
+        self.loading.borrow_mut().pop();
+    }
+
+    /// loads the module at 'path' (or reuses the already loaded one)
+    /// - builtins is the scope holding the natives, each module runs in its own scope on top of it
+    pub fn import(
+        &self,
+        path: &str,
+        line: usize,
+        builtins: Rc<Environment>,
+    ) -> Result<Rc<Module>, Err> {
+        let base_dir = self
+            .loading
+            .borrow()
+            .last()
+            .and_then(|file| file.parent().map(Path::to_path_buf));
+        let full_path = match base_dir {
+            Some(dir) => dir.join(path),
+            None => PathBuf::from(path),
+        };
+        let full_path = fs::canonicalize(&full_path).map_err(|_| {
+            Err::Interpreter(format!("Can't import '{path}': file not found."), line)
+        })?;
+
+        if let Some(module) = self.loaded.borrow().get(&full_path).cloned() {
+            return Ok(module);
+        }
+        let source = fs::read_to_string(&full_path)
+            .map_err(|e| Err::Interpreter(format!("Can't import '{path}': {e}."), line))?;
+
+        if !self.enter_file(&full_path) {
+            return Err(Err::Interpreter(
+                format!("Circular import of '{path}'."),
+                line,
+            ));
+        }
+        let env = Rc::new(Environment::new(Some(builtins)));
+        let result = run_module(&source, path, line, Rc::clone(&env));
+        self.leave_file();
+        result?;
+
+        let module = Rc::new(Module {
+            name: path.to_string(),
+            env,
+        });
+        self.loaded
+            .borrow_mut()
+            .insert(full_path, Rc::clone(&module));
+        Ok(module)
+    }
+}
+
+impl PartialEq for Modules {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+/// runs all top-level statements of a module. Syntax errors in it fail the import.
+/// - runtime errors keep the line inside the module, the message names the module they come from
+fn run_module(source: &str, path: &str, line: usize, env: Rc<Environment>) -> Result<(), Err> {
+    let lexer = new_scanner(source);
+    let (tokens, mut errors) = lexer.results();
+    let ast = AST::new(tokens);
+    errors.extend(ast.errors);
+    if let Some(e) = errors.first() {
+        return Err(Err::Interpreter(
+            format!("Can't import '{path}': {e}"),
+            line,
+        ));
+    }
+    for statement in ast.root {
+        match statement?.execute(Rc::clone(&env)) {
+            Err(Err::Interpreter(message, line)) => {
+                return Err(Err::Interpreter(format!("{message} (in '{path}')"), line))
+            }
+            result => result?,
+        }
+    }
+    Ok(())
+}
@@ -1,11 +1,14 @@
-use std::mem;
+use std::{collections::HashMap, mem};
 
 use crate::{
     expressions::{
-        BinaryExpr, Expr, FnCallExpr, GroupingExpr, LogicalExpr, UnaryExpr, Value, VarAssignExpr,
-        VarReadExpr,
+        ArmBody, BinaryExpr, CompoundAssignExpr, ConditionalExpr, Destructure,
+        DestructureAssignExpr, Expr, FnCallExpr, GetExpr, GroupingExpr, IndexExpr, IndexSetExpr,
+        InterpolationExpr, LambdaExpr, ListExpr, LogicalExpr, MapExpr, MatchArm, MatchExpr,
+        Pattern, RangeExpr, SetExpr, SpreadExpr, SuperExpr, ThisExpr, UnaryExpr, Value,
+        VarAssignExpr, VarReadExpr,
     },
-    statements::{FunctionStatement, Statement},
+    statements::{ClassStatement, FunctionStatement, Param, Statement, TryStatement},
     types::{Err, Token, TokenType as Type},
 };
 
@@ -62,22 +65,61 @@ struct Parser<'a> {
     /// Index to current token
     current: usize,
     errors: Vec<Err>,
+    /// are we inside a class body? (to reject 'this' outside of methods)
+    current_class: ClassType,
+    /// what kind of function body are we in? (to reject returning a value from 'init')
+    current_function: FunctionType,
+    /// how many loops are we nested in? (to reject 'break' outside of loops)
+    loop_depth: usize,
+    /// the names declared in each scope we are in, true for constants (to reject assigning to them)
+    scopes: Vec<HashMap<String, bool>>,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassType {
+    None,
+    Class,
+    Subclass,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FunctionType {
+    None,
+    Function,
+    Method,
+    Initializer,
+}
+
 impl<'a> Parser<'a> {
     fn new(tokens: &'a Vec<Token>) -> Self {
         Self {
             tokens,
             current: 0,
             errors: vec![],
+            current_class: ClassType::None,
+            current_function: FunctionType::None,
+            loop_depth: 0,
+            scopes: vec![HashMap::new()],
         }
     }
 
     fn parse(&mut self) -> Vec<Result<Statement, Err>> {
         let mut statements = vec![];
         while !self.is_at_end() {
-            statements.push(self.declaration());
+            let errors_before = self.errors.len();
+            // imports are only allowed at the top level of a file
+            let statement = match self.expect(vec![Type::Import]) {
+                true => self.import_declaration(),
+                false => self.declaration(),
+            };
+            // errors reported while parsing could go on (like assigning to a constant)
+            // still make the whole statement invalid, so it never gets executed
+            statements.push(match (statement, self.errors.get(errors_before)) {
+                (Ok(_), Some(e)) => Err(e.clone()),
+                (statement, _) => statement,
+            });
         }
-        return statements;
+        statements
     }
 }
 
@@ -117,6 +159,14 @@ impl<'a> Parser<'a> {
         mem::discriminant(*&typ_check) == mem::discriminant(*&&typ) // because String("1") != String("s") otherwise!
     }
 
+    /// checks type of the token after the current-token == args. does not consume tokens.
+    fn check_next(&self, typ: Type) -> bool {
+        match self.tokens.get(self.current + 1) {
+            Some(token) => mem::discriminant(&token.typ) == mem::discriminant(&typ),
+            None => false,
+        }
+    }
+
     /// also known as: match()
     /// - checks if current token of any provided types
     /// - consumes token uppon success
@@ -131,6 +181,40 @@ impl<'a> Parser<'a> {
         false
     }
 
+    /// remembers a declared name in the current scope. Constants can't be declared again in the same scope.
+    fn declare(&mut self, name: &str, constant: bool, line: usize) {
+        let Some(scope) = self.scopes.last_mut() else {
+            return;
+        };
+        if scope.insert(name.to_string(), constant) == Some(true) {
+            // keep the name constant, so later assignments to it get reported as well
+            scope.insert(name.to_string(), true);
+            let e = Err::Parser(format!("Can't redeclare constant '{name}'."), line);
+            self.errors.push(e);
+        }
+    }
+
+    /// runs parse inside a new scope, that already holds the names (like parameters)
+    fn scoped<T>(&mut self, names: Vec<String>, parse: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes
+            .push(names.into_iter().map(|name| (name, false)).collect());
+        let result = parse(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// reports assigning to a name that was declared 'const' in a scope we can see.
+    /// - parsing goes on, the parser still is in a valid state. The surrounding top-level statement
+    ///   becomes an error in parse() and never runs
+    /// - constants from other files or earlier prompt lines are caught at runtime instead
+    fn check_not_constant(&mut self, name: &str, line: usize) {
+        let constant = self.scopes.iter().rev().find_map(|scope| scope.get(name));
+        if constant == Some(&true) {
+            let e = Err::Parser(format!("Can't assign to constant '{name}'."), line);
+            self.errors.push(e);
+        }
+    }
+
     /// pushes error msg to the stack of errors, also returns a Error-Expression
     fn error_expr(&mut self, msg: &str) -> Err {
         self.errors.push(Err::Parser(msg.into(), self.peek().line));
@@ -144,18 +228,141 @@ impl<'a> Parser<'a> {
 
 impl<'a> Parser<'a> {
     fn declaration(&mut self) -> Result<Statement, Err> {
-        if self.expect(vec![Type::Fun]) {
-            return self.function();
+        if self.expect(vec![Type::Class]) {
+            return self.class_declaration();
         }
-        if self.expect(vec![Type::Var]) {
+        if self.expect(vec![Type::Enum]) {
+            return self.enum_declaration();
+        }
+        // 'fun name(...)' declares a function, while 'fun (...)' is an anonymous function expression
+        if self.check(Type::Fun) && self.check_next(Type::Identifier) {
+            self.advance();
+            let function = self.function(FunctionType::Function)?;
+            self.declare(&function.name, false, function.line);
+            return Ok(Statement::FunctionSt(function));
+        }
+        if self.expect(vec![Type::Var, Type::Const]) {
             return self.var_declaration();
         }
         return self.statement();
     }
 
+    /// import "path/to/file.lox" as IDENTIFIER;
+    fn import_declaration(&mut self) -> Result<Statement, Err> {
+        let line = self.previous().line;
+        let path = match &self.peek().typ {
+            Type::String(path) => path.clone(),
+            _ => return Err(self.error_expr("Expect path string after 'import'.")),
+        };
+        self.advance();
+        self.consume(Type::As, "Expect 'as' after import path.")?;
+        let name = self
+            .consume(Type::Identifier, "Expect module name after 'as'.")?
+            .lexeme
+            .to_string();
+        self.consume(Type::Semicolon, "Expect ';' after import.")?;
+        self.declare(&name, false, line);
+        Ok(Statement::ImportSt { path, name, line })
+    }
+
+    /// class IDENTIFIER optional'<'SUPERCLASS { ...methods }
+    fn class_declaration(&mut self) -> Result<Statement, Err> {
+        let token = self.consume(Type::Identifier, "Expect class name.")?;
+        let (name, line) = (token.lexeme.to_string(), token.line);
+        self.declare(&name, false, line);
+        let mut superclass = None;
+        if self.expect(vec![Type::Less]) {
+            let token = self.consume(Type::Identifier, "Expect superclass name.")?;
+            superclass = Some(VarReadExpr {
+                name: token.lexeme.to_string(),
+                line: token.line,
+            });
+        }
+        self.consume(Type::OpenBrace, "Expect '{' before class body.")?;
+
+        let enclosing_class = self.current_class;
+        self.current_class = match superclass {
+            Some(_) => ClassType::Subclass,
+            None => ClassType::Class,
+        };
+        let mut methods = Vec::new();
+        while !self.check(Type::CloseBrace) && !self.is_at_end() {
+            let kind = match self.peek().lexeme {
+                "init" => FunctionType::Initializer,
+                _ => FunctionType::Method,
+            };
+            match self.function(kind) {
+                Ok(method) => methods.push(method),
+                Err(e) => {
+                    self.current_class = enclosing_class;
+                    return Err(e);
+                }
+            }
+        }
+        self.current_class = enclosing_class;
+
+        self.consume(Type::CloseBrace, "Expect '}' after class body.")?;
+        Ok(Statement::ClassSt(ClassStatement {
+            name,
+            superclass,
+            methods,
+            line,
+        }))
+    }
+
+    /// enum IDENTIFIER { VARIANT optional'('FIELDS')' , ... }
+    fn enum_declaration(&mut self) -> Result<Statement, Err> {
+        let token = self.consume(Type::Identifier, "Expect enum name.")?;
+        let (name, line) = (token.lexeme.to_string(), token.line);
+        self.declare(&name, false, line);
+        self.consume(Type::OpenBrace, "Expect '{' before enum variants.")?;
+        let mut variants: Vec<(String, Vec<String>)> = vec![];
+        while !self.check(Type::CloseBrace) && !self.is_at_end() {
+            let token = self.consume(Type::Identifier, "Expect variant name.")?;
+            let (variant, variant_line) = (token.lexeme.to_string(), token.line);
+            if variants.iter().any(|(other, _)| *other == variant) {
+                let msg = format!("Duplicate variant '{variant}' in enum '{name}'.");
+                self.errors.push(Err::Parser(msg, variant_line));
+            }
+            let mut fields = vec![];
+            if self.expect(vec![Type::OpenParen]) {
+                loop {
+                    let token = self.consume(Type::Identifier, "Expect field name.")?;
+                    let (field, field_line) = (token.lexeme.to_string(), token.line);
+                    if fields.contains(&field) {
+                        let msg =
+                            format!("Duplicate field '{field}' in variant '{name}.{variant}'.");
+                        self.errors.push(Err::Parser(msg, field_line));
+                    }
+                    fields.push(field);
+                    if !self.expect(vec![Type::Comma]) {
+                        break;
+                    }
+                }
+                self.consume(Type::CloseParen, "Expect ')' after variant fields.")?;
+            }
+            variants.push((variant, fields));
+            if !self.expect(vec![Type::Comma]) {
+                break;
+            }
+        }
+        self.consume(Type::CloseBrace, "Expect '}' after enum variants.")?;
+        Ok(Statement::EnumSt {
+            name,
+            variants,
+            line,
+        })
+    }
+
     /// var IDENTIFIER optionalINITIALVALUE ;
+    /// const IDENTIFIER = VALUE ; (constants can't be left uninitialized)
     fn var_declaration(&mut self) -> Result<Statement, Err> {
+        let constant = matches!(self.previous().typ, Type::Const);
+        if self.check(Type::OpenBracket) || self.check(Type::OpenBrace) {
+            return self.destructuring_declaration(constant);
+        }
         let name: String;
+        let line = self.peek().line;
         if let Ok(token) = self.consume(Type::Identifier, "Expected variable name after var") {
             name = token.lexeme.to_string();
         } else {
@@ -164,25 +371,111 @@ impl<'a> Parser<'a> {
         let mut initializer = Expr::Literal(Value::Nil); // null if not initialized
         if self.expect(vec![Type::Equal]) {
             initializer = self.expression()?;
+        } else if constant {
+            self.error_expr(&format!("Constant '{name}' needs a value."));
         }
         self.consume(Type::Semicolon, "Expect ';' after variable declaration")?;
-        return Ok(Statement::VariableSt(name, initializer));
+        self.declare(&name, constant, line);
+        Ok(Statement::VariableSt {
+            name,
+            value: initializer,
+            constant,
+            line,
+        })
+    }
+
+    /// var [a, b, ...rest] = xs;   or   var {name, age: years} = record;
+    fn destructuring_declaration(&mut self, constant: bool) -> Result<Statement, Err> {
+        let line = self.peek().line;
+        let target = self.destructure_pattern()?;
+        self.consume(Type::Equal, "Expect '=' after destructuring pattern.")?;
+        let value = self.expression()?;
+        self.consume(Type::Semicolon, "Expect ';' after variable declaration")?;
+        for name in destructure_names(&target) {
+            self.declare(&name, constant, line);
+        }
+        Ok(Statement::DestructureSt {
+            target,
+            value,
+            constant,
+            line,
+        })
+    }
+
+    fn destructure_pattern(&mut self) -> Result<Destructure, Err> {
+        if self.expect(vec![Type::OpenBracket]) {
+            let mut elements = vec![];
+            let mut rest = None;
+            while !self.check(Type::CloseBracket) {
+                if self.expect(vec![Type::DotDotDot]) {
+                    let name = self.consume(Type::Identifier, "Expect name after '...'.")?;
+                    rest = Some(name.lexeme.to_string());
+                    break; // the rest has to be the last one
+                }
+                elements.push(self.destructure_pattern()?);
+                if !self.expect(vec![Type::Comma]) {
+                    break;
+                }
+            }
+            self.consume(
+                Type::CloseBracket,
+                "Expect ']' after destructuring pattern.",
+            )?;
+            return Ok(Destructure::List { elements, rest });
+        }
+        if self.expect(vec![Type::OpenBrace]) {
+            let mut entries = vec![];
+            while !self.check(Type::CloseBrace) {
+                let key = self
+                    .consume(
+                        Type::Identifier,
+                        "Expect key name in destructuring pattern.",
+                    )?
+                    .lexeme
+                    .to_string();
+                // '{age: years}' binds the key age to the name years
+                let target = match self.expect(vec![Type::Colon]) {
+                    true => self.destructure_pattern()?,
+                    false => Destructure::Name(key.clone()),
+                };
+                entries.push((key, target));
+                if !self.expect(vec![Type::Comma]) {
+                    break;
+                }
+            }
+            self.consume(Type::CloseBrace, "Expect '}' after destructuring pattern.")?;
+            return Ok(Destructure::Map(entries));
+        }
+        let name = self.consume(Type::Identifier, "Expected variable name after var")?;
+        Ok(Destructure::Name(name.lexeme.to_string()))
     }
 
     fn while_statement(&mut self) -> Result<Statement, Err> {
         self.consume(Type::OpenParen, "Expect '(' after 'while'.")?;
         let condition = self.expression()?;
         self.consume(Type::CloseParen, "Expect ')' after while-condition.")?;
-        let body = self.statement()?;
+        let body = self.loop_body()?;
         return Ok(Statement::While {
             condition: condition,
             body: Box::new(body),
+            increment: None,
         });
     }
 
+    /// parses the body of a loop, inside of it 'break' and 'continue' are allowed
+    fn loop_body(&mut self) -> Result<Statement, Err> {
+        self.loop_depth += 1;
+        let body = self.statement();
+        self.loop_depth -= 1;
+        body
+    }
+
     fn for_statement(&mut self) -> Result<Statement, Err> {
         // for(initializer; condition; increment){body}     'for(var i=0; i<10; i++){print i;}'
         self.consume(Type::OpenParen, "Expect '(' after 'for'.")?;
+        if self.check(Type::Identifier) && self.check_next(Type::In) {
+            return self.for_in_statement();
+        }
         // the optional initializer: ex 'var i=0;'
         let initializer: Option<Statement>;
         if self.expect(vec![Type::Semicolon]) {
@@ -207,21 +500,18 @@ impl<'a> Parser<'a> {
         };
         self.consume(Type::CloseParen, "Expect ')' after for-loop clauses.")?;
         // the body enclosed in {...}
-        let mut body = self.statement();
+        let mut body = self.loop_body();
 
         // desugaring = rebuilding our for loop with existing while loop and var, assign, block etc:
-        if let Some(increment) = increment {
-            let artificial_body: Vec<Result<Statement, Err>> =
-                vec![body, Ok(Statement::ExprSt(increment))];
-            body = Ok(Statement::BlockSt(artificial_body));
-        }
+        // - the increment stays separate from the body, so it still runs after a 'continue'
         if condition == None {
             condition = Some(Expr::Literal(Value::Boolean(true)));
         }
         // save to unwrap here since we know we guarded against it (we know they exist)
         body = Ok(Statement::While {
             condition: condition.unwrap(),
-            body: Box::new(body.unwrap()),
+            body: Box::new(body?),
+            increment,
         });
         if let Some(initializer) = initializer {
             body = Ok(Statement::BlockSt(vec![Ok(initializer), body]));
@@ -229,9 +519,25 @@ impl<'a> Parser<'a> {
         return body;
     }
 
+    /// for (item in iterable) {body}     'for (c in "abc") print c;'
+    fn for_in_statement(&mut self) -> Result<Statement, Err> {
+        let name = self.advance().lexeme.to_string();
+        let line = self.advance().line; // the 'in'
+        let iterable = self.expression()?;
+        self.consume(Type::CloseParen, "Expect ')' after for-loop iterable.")?;
+        let body = self.scoped(vec![name.clone()], |parser| parser.loop_body())?;
+        Ok(Statement::ForIn {
+            name,
+            iterable,
+            body: Box::new(body),
+            line,
+        })
+    }
+
     fn statement(&mut self) -> Result<Statement, Err> {
         if self.expect(vec![Type::For]) {
-            return self.for_statement();
+            // the loop variable only lives inside the loop
+            return self.scoped(vec![], |parser| parser.for_statement());
         }
         if self.expect(vec![Type::If]) {
             return self.if_statement();
@@ -245,12 +551,107 @@ impl<'a> Parser<'a> {
         if self.expect(vec![Type::While]) {
             return self.while_statement();
         }
+        if self.expect(vec![Type::Delete]) {
+            return self.delete_statement();
+        }
+        if self.expect(vec![Type::Break, Type::Continue]) {
+            return self.loop_jump_statement();
+        }
+        if self.expect(vec![Type::Try]) {
+            return self.try_statement();
+        }
+        if self.expect(vec![Type::Throw]) {
+            return self.throw_statement();
+        }
+        if self.expect(vec![Type::Import]) {
+            let line = self.previous().line;
+            self.import_declaration()?;
+            let e = Err::Parser(
+                "Imports are only allowed at the top level of a file.".into(),
+                line,
+            );
+            self.errors.push(e.clone());
+            return Err(e);
+        }
+        // a match used as statement needs no ';' after its closing '}'
+        if self.expect(vec![Type::Match]) {
+            let expr = self.match_expression()?;
+            self.expect(vec![Type::Semicolon]);
+            return Ok(Statement::ExprSt(expr));
+        }
         if self.expect(vec![Type::OpenBrace]) {
             return Ok(Statement::BlockSt(self.block()));
         }
         return self.expression_statement();
     }
 
+    /// break; or continue; only allowed inside the body of a loop
+    fn loop_jump_statement(&mut self) -> Result<Statement, Err> {
+        let typ = self.previous().typ.clone();
+        let (keyword, line) = (self.previous().lexeme.to_string(), self.previous().line);
+        self.consume(Type::Semicolon, "Expect ';' after loop jump.")?;
+        if self.loop_depth == 0 {
+            let e = Err::Parser(format!("Can't use '{keyword}' outside of a loop."), line);
+            self.errors.push(e.clone());
+            return Err(e);
+        }
+        match typ {
+            Type::Break => Ok(Statement::BreakSt),
+            _ => Ok(Statement::ContinueSt),
+        }
+    }
+
+    /// try { ... } catch (name) { ... } finally { ... }
+    fn try_statement(&mut self) -> Result<Statement, Err> {
+        self.consume(Type::OpenBrace, "Expect '{' after 'try'.")?;
+        let body = self.block();
+
+        let mut catch = None;
+        if self.expect(vec![Type::Catch]) {
+            self.consume(Type::OpenParen, "Expect '(' after 'catch'.")?;
+            let name = self
+                .consume(Type::Identifier, "Expect error variable name.")?
+                .lexeme
+                .to_string();
+            self.consume(Type::CloseParen, "Expect ')' after error variable name.")?;
+            self.consume(Type::OpenBrace, "Expect '{' before catch body.")?;
+            let handler = self.scoped(vec![name.clone()], |parser| parser.block());
+            catch = Some((name, handler));
+        }
+        let mut finally = None;
+        if self.expect(vec![Type::Finally]) {
+            self.consume(Type::OpenBrace, "Expect '{' after 'finally'.")?;
+            finally = Some(self.block());
+        }
+
+        if catch.is_none() && finally.is_none() {
+            return Err(self.error_expr("Expect 'catch' or 'finally' after try block."));
+        }
+        Ok(Statement::TrySt(TryStatement {
+            body,
+            catch,
+            finally,
+        }))
+    }
+
+    /// throw expression;
+    fn throw_statement(&mut self) -> Result<Statement, Err> {
+        let line = self.previous().line;
+        let value = self.expression()?;
+        self.consume(Type::Semicolon, "Expect ';' after thrown value.")?;
+        Ok(Statement::ThrowSt { value, line })
+    }
+
+    /// delete xs[index]; or delete map[key];
+    fn delete_statement(&mut self) -> Result<Statement, Err> {
+        let target = self.expression()?;
+        self.consume(Type::Semicolon, "Expect ';' after delete target.")?;
+        match target {
+            Expr::Index(index) => Ok(Statement::DeleteSt(index)),
+            _ => Err(self.error_expr("Can only delete list elements or map entries.")),
+        }
+    }
+
     fn if_statement(&mut self) -> Result<Statement, Err> {
         _ = self.consume(Type::OpenParen, "Expect '(' after 'if'.")?;
         let condition = self.expression()?;
@@ -280,11 +681,17 @@ impl<'a> Parser<'a> {
 
     fn return_statement(&mut self) -> Result<Statement, Err> {
         let keyword = self.previous().lexeme.to_string();
-        let mut value = Expr::Literal(Value::Nil);
+        let line = self.previous().line;
+        let mut value = None;
         if !self.check(Type::Semicolon) {
-            value = self.expression()?;
+            value = Some(self.expression()?);
         }
         self.consume(Type::Semicolon, "Expect ';' after return value.")?;
+        if value.is_some() && self.current_function == FunctionType::Initializer {
+            let e = Err::Parser("Can't return a value from an initializer.".into(), line);
+            self.errors.push(e.clone());
+            return Err(e);
+        }
         return Ok(Statement::ReturnSt { keyword, value });
     }
 
@@ -294,12 +701,22 @@ impl<'a> Parser<'a> {
         return Ok(Statement::ExprSt(expr));
     }
 
-    fn function(&mut self) -> Result<Statement, Err> {
+    /// functions 'fun name(a,b){...}' and methods inside a class body 'name(a,b){...}'
+    fn function(&mut self, kind: FunctionType) -> Result<FunctionStatement, Err> {
         let name = self
             .consume(Type::Identifier, "Expect function/method name.")?
             .lexeme
             .to_string();
+        self.function_body(name, kind)
+    }
 
+    /// the '(a,b){...}' part of a function. Shared between named and anonymous functions.
+    fn function_body(
+        &mut self,
+        name: String,
+        kind: FunctionType,
+    ) -> Result<FunctionStatement, Err> {
+        let line = self.previous().line;
         self.consume(Type::OpenParen, "Expect '(' after function/method name.")?;
 
         let mut params = Vec::new();
@@ -311,11 +728,38 @@ impl<'a> Parser<'a> {
                         self.peek().line,
                     ));
                 }
-                params.push(
-                    self.consume(Type::Identifier, "Expect parameter name.")?
-                        .lexeme
-                        .to_string(),
-                );
+                let rest = self.expect(vec![Type::DotDotDot]);
+                let name = self
+                    .consume(Type::Identifier, "Expect parameter name.")?
+                    .lexeme
+                    .to_string();
+                // '...rest' takes the left over arguments, so nothing can come after it
+                if rest {
+                    if !self.check(Type::CloseParen) {
+                        let msg = format!("Rest parameter '{name}' must be the last parameter.");
+                        return Err(self.error_expr(&msg));
+                    }
+                    params.push(Param {
+                        name,
+                        default: None,
+                        rest,
+                    });
+                    break;
+                }
+                let mut default = None;
+                if self.expect(vec![Type::Equal]) {
+                    default = Some(self.expression()?);
+                } else if params.iter().any(|p: &Param| p.default.is_some()) {
+                    let msg = format!(
+                        "Parameter '{name}' without default can't follow ones with defaults."
+                    );
+                    return Err(self.error_expr(&msg));
+                }
+                params.push(Param {
+                    name,
+                    default,
+                    rest,
+                });
 
                 if !self.expect(vec![Type::Comma]) {
                     break;
@@ -324,20 +768,30 @@ impl<'a> Parser<'a> {
         }
         self.consume(Type::CloseParen, "Expect ')' after parameters.")?;
         self.consume(Type::OpenBrace, "Expect '{' before function/method body.")?;
-        let body = self.block();
-        return Ok(Statement::FunctionSt(FunctionStatement {
+        let enclosing_function = self.current_function;
+        let enclosing_loop_depth = self.loop_depth;
+        self.current_function = kind;
+        self.loop_depth = 0; // can't break out of a loop that surrounds the function
+        let names = params.iter().map(|p: &Param| p.name.clone()).collect();
+        let body = self.scoped(names, |parser| parser.block());
+        self.current_function = enclosing_function;
+        self.loop_depth = enclosing_loop_depth;
+        Ok(FunctionStatement {
             name,
             params,
             body,
-        }));
+            line,
+        })
     }
 
     /// a new block/scope
     fn block(&mut self) -> Vec<Result<Statement, Err>> {
         let mut statements = Vec::<Result<Statement, Err>>::new();
+        self.scopes.push(HashMap::new());
         while !self.check(Type::CloseBrace) && !self.is_at_end() {
             statements.push(self.declaration());
         }
+        self.scopes.pop();
         if let Err(did_err) = self.consume(Type::CloseBrace, "Expect '}' after block.") {
             statements.push(Err(did_err));
         }
@@ -345,19 +799,87 @@ impl<'a> Parser<'a> {
     }
 }
 
+/// all names a destructuring binds
+fn destructure_names(target: &Destructure) -> Vec<String> {
+    match target {
+        Destructure::Name(name) => vec![name.clone()],
+        Destructure::List { elements, rest } => {
+            let mut names: Vec<String> = elements.iter().flat_map(destructure_names).collect();
+            names.extend(rest.clone());
+            names
+        }
+        Destructure::Map(entries) => entries
+            .iter()
+            .flat_map(|(_, target)| destructure_names(target))
+            .collect(),
+    }
+}
+
+/// all names a match pattern binds
+fn pattern_names(pattern: &Pattern) -> Vec<String> {
+    match pattern {
+        Pattern::Binding(name) => vec![name.clone()],
+        Pattern::List(patterns) | Pattern::Alternatives(patterns) => {
+            patterns.iter().flat_map(pattern_names).collect()
+        }
+        Pattern::Variant { fields, .. } => {
+            fields.iter().flatten().flat_map(pattern_names).collect()
+        }
+        Pattern::Wildcard | Pattern::Literal(_) => vec![],
+    }
+}
+
+/// turns the list literal left of '=' back into what it names: '[a, [b, c], ...rest]'
+fn destructure_target(expr: Expr) -> Option<Destructure> {
+    match expr {
+        Expr::VarRead(var) => Some(Destructure::Name(var.name)),
+        Expr::List(ListExpr { mut elements }) => {
+            let mut rest = None;
+            if let Some(Expr::Spread(SpreadExpr { value, .. })) = elements.last() {
+                let Expr::VarRead(var) = value.as_ref() else {
+                    return None;
+                };
+                rest = Some(var.name.clone());
+                elements.pop();
+            }
+            let elements = elements
+                .into_iter()
+                .map(destructure_target)
+                .collect::<Option<Vec<_>>>()?;
+            Some(Destructure::List { elements, rest })
+        }
+        _ => None,
+    }
+}
+
 /*
         Handling Expressions
 
 The Grammar rules sorted by precedence:
  PrioToCheck:
     expression ->   assignment;
-    assignment ->   IDENTIFIER "=" assignment | equality();
+    assignment ->   target ("=" | "+=" | "-=" | "*=" | "/=") assignment | conditional();
+    conditional ->  nil_coalescing ("?" expression ":" conditional)?;
+    nil_coalescing -> logical_or ("??" logical_or)*;
             1       ==  !=              equality()      ex: true != false
-            2       >   >=  <   <=      comparison()    ex: 3>2
-            3       +   -               term()          ex: 1+2-3
-            4       *   /               factor()        ex: 1*3  or 10/5
-            5       -   !               unary()         ex: -(3) or !false
-            6       ()  true,false...   primary()       ex: Number(1.2) or "string" or (...) or nil
+            2       >   >=  <   <=  in  comparison()    ex: 3>2 or "key" in map
+            3       ..  ..=  step       range()         ex: 0..10 or 1..=n step 2
+            4       |                   bitwise_or()    ex: flags | 0b100
+            5       ^                   bitwise_xor()   ex: a ^ b
+            6       &                   bitwise_and()   ex: flags & 0xFF
+            7       <<  >>              shift()         ex: 1 << 4
+            8       +   -               term()          ex: 1+2-3
+            9       *   /   %   ~/      factor()        ex: 1*3  or 10/5 or 7%2 or 7~/2
+            10      -   !   ~   ++  --  unary()         ex: -(3) or !false or ~0 or ++i
+            11      **                  exponent()      ex: 2**3 (right associative, -2**2 is -4)
+            12      ++  --              postfix()       ex: i++ (evaluates to the old value)
+            13      ()  true,false...   primary()       ex: Integer(1) or Number(1.2) or "string" or (...) or nil
+
+    match ->        "match" "(" expression ")" "{" (arm ","?)* "}";
+    arm ->          pattern ("if" expression)? "=>" (block | expression);
+    pattern ->      single ("|" single)*;
+    single ->       "_" | IDENTIFIER | literal | "-" number | "[" (pattern ("," pattern)*)? "]"
+                    | IDENTIFIER "." IDENTIFIER ("(" (pattern ("," pattern)*)? ")")?;
 */
 
 impl<'a> Parser<'a> {
@@ -366,20 +888,126 @@ impl<'a> Parser<'a> {
     }
 
     fn assignment(&mut self) -> Result<Expr, Err> {
-        let expr = self.logical_or();
+        let expr = self.conditional();
         // we parse left side, if next is '=' then we know we are trying to assign:
         if self.expect(vec![Type::Equal]) {
             //let equals = self.previous();
+            let line = self.previous().line;
             let value = self.assignment();
-            if let Expr::VarRead(var) = expr? {
-                let name = var.name;
-                return Ok(Expr::VarAssign(VarAssignExpr::new(name, value?)));
+            match expr? {
+                Expr::VarRead(var) => {
+                    self.check_not_constant(&var.name, line);
+                    let name = var.name;
+                    return Ok(Expr::VarAssign(VarAssignExpr::new(name, value?, var.line)));
+                }
+                Expr::Get(get) => {
+                    return Ok(Expr::Set(SetExpr {
+                        object: get.object,
+                        name: get.name,
+                        value: Box::new(value?),
+                        line: get.line,
+                    }));
+                }
+                Expr::Index(index) => {
+                    return Ok(Expr::IndexSet(IndexSetExpr {
+                        object: index.object,
+                        index: index.index,
+                        value: Box::new(value?),
+                        line: index.line,
+                    }));
+                }
+                // '[a, b] = [b, a]' the list literal on the left becomes the destructuring target
+                list @ Expr::List(_) => {
+                    if let Some(target) = destructure_target(list) {
+                        for name in destructure_names(&target) {
+                            self.check_not_constant(&name, line);
+                        }
+                        return Ok(Expr::DestructureAssign(DestructureAssignExpr {
+                            target,
+                            value: Box::new(value?),
+                            line,
+                        }));
+                    }
+                }
+                _ => {}
             }
             return Err(self.error_expr("Invalid assignment target."));
         }
+        if self.expect(vec![
+            Type::PlusEqual,
+            Type::MinusEqual,
+            Type::StarEqual,
+            Type::SlashEqual,
+        ]) {
+            let (operator, line) = (self.previous().typ.clone(), self.previous().line);
+            let value = self.assignment()?;
+            return self.compound_assignment(expr?, operator, value, false, line);
+        }
         return expr;
     }
 
+    /// 'x += 1', '++x' or 'x--' for any assignable target: variables, fields or indexes
+    fn compound_assignment(
+        &mut self,
+        target: Expr,
+        operator: Type,
+        value: Expr,
+        postfix: bool,
+        line: usize,
+    ) -> Result<Expr, Err> {
+        if !matches!(target, Expr::VarRead(_) | Expr::Get(_) | Expr::Index(_)) {
+            let e = Err::Parser(format!("Invalid target for '{operator}'."), line);
+            self.errors.push(e.clone());
+            return Err(e);
+        }
+        if let Expr::VarRead(var) = &target {
+            self.check_not_constant(&var.name, line);
+        }
+        let operator = match operator {
+            Type::PlusEqual | Type::PlusPlus => Type::Plus,
+            Type::MinusEqual | Type::MinusMinus => Type::Minus,
+            Type::StarEqual => Type::Star,
+            _ => Type::Slash,
+        };
+        Ok(Expr::CompoundAssign(CompoundAssignExpr {
+            target: Box::new(target),
+            operator,
+            value: Box::new(value),
+            postfix,
+            line,
+        }))
+    }
+
+    /// cond ? a : b is right associative: a ? b : c ? d : e == a ? b : (c ? d : e)
+    fn conditional(&mut self) -> Result<Expr, Err> {
+        let condition = self.nil_coalescing()?;
+        if self.expect(vec![Type::Question]) {
+            let then_ = self.expression()?;
+            self.consume(Type::Colon, "Expect ':' after then branch of conditional.")?;
+            let else_ = self.conditional()?;
+            return Ok(Expr::Conditional(ConditionalExpr {
+                condition: Box::new(condition),
+                then_: Box::new(then_),
+                else_: Box::new(else_),
+            }));
+        }
+        Ok(condition)
+    }
+
+    fn nil_coalescing(&mut self) -> Result<Expr, Err> {
+        let mut expr = self.logical_or();
+        while self.expect(vec![Type::QuestionQuestion]) {
+            let token = self.previous().typ.clone();
+            let right = self.logical_or();
+            expr = Ok(Expr::Logical(LogicalExpr {
+                left: Box::new(expr?),
+                token,
+                right: Box::new(right?),
+            }))
+        }
+        expr
+    }
+
     fn logical_or(&mut self) -> Result<Expr, Err> {
         let mut expr = self.logical_and();
         while self.expect(vec![Type::Or]) {
@@ -414,6 +1042,7 @@ impl<'a> Parser<'a> {
             expr = Ok(Expr::Binary(BinaryExpr {
                 left: Box::new(expr?),
                 token: self.previous().typ.clone(),
+                line: self.previous().line,
                 right: Box::new(self.comparison()?),
             }));
         }
@@ -421,17 +1050,98 @@ impl<'a> Parser<'a> {
     }
 
     fn comparison(&mut self) -> Result<Expr, Err> {
-        let mut expr = self.term();
+        let mut expr = self.range();
 
         while self.expect(vec![
             Type::Greater,
             Type::GreaterEqual,
             Type::Less,
             Type::LessEqual,
+            Type::In,
         ]) {
             expr = Ok(Expr::Binary(BinaryExpr {
                 left: Box::new(expr?),
                 token: self.previous().typ.clone(),
+                line: self.previous().line,
+                right: Box::new(self.range()?),
+            }));
+        }
+        expr
+    }
+
+    /// 'start..end' or 'start..=end'. The 'step' after it is no keyword, so it stays a usable name
+    fn range(&mut self) -> Result<Expr, Err> {
+        let start = self.bitwise_or()?;
+        if !self.expect(vec![Type::DotDot, Type::DotDotEqual]) {
+            return Ok(start);
+        }
+        let inclusive = matches!(self.previous().typ, Type::DotDotEqual);
+        let line = self.previous().line;
+        let end = self.bitwise_or()?;
+        let mut step = None;
+        if self.check(Type::Identifier) && self.peek().lexeme == "step" {
+            self.advance();
+            step = Some(Box::new(self.bitwise_or()?));
+        }
+        Ok(Expr::Range(RangeExpr {
+            start: Box::new(start),
+            end: Box::new(end),
+            step,
+            inclusive,
+            line,
+        }))
+    }
+
+    fn bitwise_or(&mut self) -> Result<Expr, Err> {
+        let mut expr = self.bitwise_xor();
+
+        while self.expect(vec![Type::Pipe]) {
+            expr = Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr?),
+                token: self.previous().typ.clone(),
+                line: self.previous().line,
+                right: Box::new(self.bitwise_xor()?),
+            }));
+        }
+        expr
+    }
+
+    fn bitwise_xor(&mut self) -> Result<Expr, Err> {
+        let mut expr = self.bitwise_and();
+
+        while self.expect(vec![Type::Caret]) {
+            expr = Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr?),
+                token: self.previous().typ.clone(),
+                line: self.previous().line,
+                right: Box::new(self.bitwise_and()?),
+            }));
+        }
+        expr
+    }
+
+    fn bitwise_and(&mut self) -> Result<Expr, Err> {
+        let mut expr = self.shift();
+
+        while self.expect(vec![Type::Ampersand]) {
+            expr = Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr?),
+                token: self.previous().typ.clone(),
+                line: self.previous().line,
+                right: Box::new(self.shift()?),
+            }));
+        }
+        expr
+    }
+
+    fn shift(&mut self) -> Result<Expr, Err> {
+        let mut expr = self.term();
+
+        while self.expect(vec![Type::LessLess, Type::GreaterGreater]) {
+            expr = Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr?),
+                token: self.previous().typ.clone(),
+                line: self.previous().line,
                 right: Box::new(self.term()?),
             }));
         }
@@ -445,6 +1155,7 @@ impl<'a> Parser<'a> {
             expr = Ok(Expr::Binary(BinaryExpr {
                 left: Box::new(expr?),
                 token: self.previous().typ.clone(),
+                line: self.previous().line,
                 right: Box::new(self.factor()?),
             }));
         }
@@ -454,10 +1165,16 @@ impl<'a> Parser<'a> {
     fn factor(&mut self) -> Result<Expr, Err> {
         let mut expr = self.unary();
 
-        while self.expect(vec![Type::Slash, Type::Star]) {
+        while self.expect(vec![
+            Type::Slash,
+            Type::Star,
+            Type::Percent,
+            Type::TildeSlash,
+        ]) {
             expr = Ok(Expr::Binary(BinaryExpr {
                 left: Box::new(expr?),
                 token: self.previous().typ.clone(),
+                line: self.previous().line,
                 right: Box::new(self.unary()?),
             }));
         }
@@ -465,26 +1182,75 @@ impl<'a> Parser<'a> {
     }
 
     fn unary(&mut self) -> Result<Expr, Err> {
-        if self.expect(vec![Type::Exclamation, Type::Minus]) {
+        if self.expect(vec![Type::Exclamation, Type::Minus, Type::Tilde]) {
             return Ok(Expr::Unary(UnaryExpr {
                 token: self.previous().typ.clone(),
+                line: self.previous().line,
+                right: Box::new(self.unary()?),
+            }));
+        }
+        if self.expect(vec![Type::PlusPlus, Type::MinusMinus]) {
+            let (operator, line) = (self.previous().typ.clone(), self.previous().line);
+            let target = self.unary()?;
+            let one = Expr::Literal(Value::Integer(1));
+            return self.compound_assignment(target, operator, one, false, line);
+        }
+        self.exponent()
+    }
+
+    /// binds tighter than unary on its left, but the exponent itself may be negative: 2 ** -1
+    fn exponent(&mut self) -> Result<Expr, Err> {
+        let expr = self.postfix()?;
+        if self.expect(vec![Type::StarStar]) {
+            return Ok(Expr::Binary(BinaryExpr {
+                left: Box::new(expr),
+                token: self.previous().typ.clone(),
+                line: self.previous().line,
                 right: Box::new(self.unary()?),
             }));
         }
-        self.call()
+        Ok(expr)
+    }
+
+    /// 'x++' or 'xs[i]--' evaluate to the value before the change
+    fn postfix(&mut self) -> Result<Expr, Err> {
+        let expr = self.call()?;
+        if self.expect(vec![Type::PlusPlus, Type::MinusMinus]) {
+            let (operator, line) = (self.previous().typ.clone(), self.previous().line);
+            let one = Expr::Literal(Value::Integer(1));
+            return self.compound_assignment(expr, operator, one, true, line);
+        }
+        Ok(expr)
     }
 
     fn finish_call(&mut self, callee: Expr) -> Result<Expr, Err> {
         let mut arguments = Vec::new();
+        let mut named = Vec::new();
         if !self.check(Type::CloseParen) {
             loop {
-                if arguments.len() >= 255 {
+                if arguments.len() + named.len() >= 255 {
                     return Err(Err::Parser(
                         "Can't have more than 255 arguments.".into(),
                         self.peek().line,
                     ));
                 }
-                arguments.push(self.expression()?);
+                // named argument 'name: value'
+                if self.check(Type::Identifier) && self.check_next(Type::Colon) {
+                    let name = self.advance().lexeme.to_string();
+                    self.advance();
+                    named.push((name, self.expression()?));
+                } else if !named.is_empty() {
+                    return Err(self.error_expr("Positional arguments can't follow named ones."));
+                } else if self.expect(vec![Type::DotDotDot]) {
+                    // spreading '...xs' passes every value of xs as its own argument
+                    let line = self.previous().line;
+                    arguments.push(Expr::Spread(SpreadExpr {
+                        value: Box::new(self.expression()?),
+                        line,
+                    }));
+                } else {
+                    arguments.push(self.expression()?);
+                }
                 if !self.expect(vec![Type::Comma]) {
                     break;
                 } // basically do while...
@@ -495,6 +1261,8 @@ impl<'a> Parser<'a> {
             callee: Box::new(callee),
             paren: paren.typ.clone(),
             arguments: arguments,
+            named,
+            line: paren.line,
         }))
     }
 
@@ -503,6 +1271,22 @@ impl<'a> Parser<'a> {
         loop {
             if self.expect(vec![Type::OpenParen]) {
                 expr = self.finish_call(expr?);
+            } else if self.expect(vec![Type::Dot]) {
+                let token = self.consume(Type::Identifier, "Expect property name after '.'.")?;
+                expr = Ok(Expr::Get(GetExpr {
+                    object: Box::new(expr?),
+                    name: token.lexeme.to_string(),
+                    line: token.line,
+                }));
+            } else if self.expect(vec![Type::OpenBracket]) {
+                let line = self.previous().line;
+                let index = self.expression()?;
+                self.consume(Type::CloseBracket, "Expect ']' after index.")?;
+                expr = Ok(Expr::Index(IndexExpr {
+                    object: Box::new(expr?),
+                    index: Box::new(index),
+                    line,
+                }));
             } else {
                 break;
             }
@@ -517,7 +1301,27 @@ impl<'a> Parser<'a> {
             Type::False => Ok(Expr::Literal(Value::Boolean(false))),
             Type::Nil => Ok(Expr::Literal(Value::Nil)),
             Type::Number(nr) => Ok(Expr::Literal(Value::Number(*nr))),
+            Type::Integer(nr) => Ok(Expr::Literal(Value::Integer(*nr))),
             Type::String(st) => Ok(Expr::Literal(Value::String(st.clone()))),
+            // "a ${x} b" is lexed as: Interpolation("a ") x String(" b")
+            Type::Interpolation(first) => {
+                let mut parts = vec![Expr::Literal(Value::String(first.clone()))];
+                loop {
+                    parts.push(self.expression()?);
+                    let part = match &self.peek().typ {
+                        Type::Interpolation(part) | Type::String(part) => part.clone(),
+                        _ => {
+                            return Err(self.error_expr("Expect '}' after interpolated expression."))
+                        }
+                    };
+                    let is_last = matches!(self.advance().typ, Type::String(_));
+                    parts.push(Expr::Literal(Value::String(part)));
+                    if is_last {
+                        break;
+                    }
+                }
+                Ok(Expr::Interpolation(InterpolationExpr { parts }))
+            }
             Type::OpenParen => {
                 let expr = self.expression(); // back to the top and parse what is inside the parenthesis
                 if let Err(e) =
@@ -530,9 +1334,82 @@ impl<'a> Parser<'a> {
                     expr: Box::new(expr?),
                 }))
             }
+            Type::OpenBracket => {
+                let mut elements = Vec::new();
+                if !self.check(Type::CloseBracket) {
+                    loop {
+                        if self.expect(vec![Type::DotDotDot]) {
+                            let line = self.previous().line;
+                            elements.push(Expr::Spread(SpreadExpr {
+                                value: Box::new(self.expression()?),
+                                line,
+                            }));
+                        } else {
+                            elements.push(self.expression()?);
+                        }
+                        if !self.expect(vec![Type::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Type::CloseBracket, "Expect ']' after list elements.")?;
+                Ok(Expr::List(ListExpr { elements }))
+            }
+            // a '{' in expression position is always a map. (blocks only start at statements)
+            Type::OpenBrace => {
+                let line = self.previous().line;
+                let mut entries = Vec::new();
+                if !self.check(Type::CloseBrace) {
+                    loop {
+                        let key = self.expression()?;
+                        self.consume(Type::Colon, "Expect ':' after map key.")?;
+                        entries.push((key, self.expression()?));
+                        if !self.expect(vec![Type::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Type::CloseBrace, "Expect '}' after map entries.")?;
+                Ok(Expr::Map(MapExpr { entries, line }))
+            }
+            Type::Match => self.match_expression(),
+            // anonymous function: 'fun (a, b) { return a + b; }'
+            Type::Fun => {
+                let function_st = self.function_body("lambda".into(), FunctionType::Function)?;
+                Ok(Expr::Lambda(LambdaExpr { function_st }))
+            }
             Type::Identifier => Ok(Expr::VarRead(VarReadExpr {
                 name: self.previous().lexeme.to_string(),
+                line: self.previous().line,
             })),
+            Type::This => {
+                if self.current_class == ClassType::None {
+                    return Err(self.error_expr("Can't use 'this' outside of a class."));
+                }
+                Ok(Expr::This(ThisExpr {
+                    line: self.previous().line,
+                }))
+            }
+            Type::Super => {
+                let line = self.previous().line;
+                self.consume(Type::Dot, "Expect '.' after 'super'.")?;
+                let method = self
+                    .consume(Type::Identifier, "Expect superclass method name.")?
+                    .lexeme
+                    .to_string();
+                match self.current_class {
+                    ClassType::None => {
+                        return Err(self.error_expr("Can't use 'super' outside of a class."))
+                    }
+                    ClassType::Class => {
+                        return Err(
+                            self.error_expr("Can't use 'super' in a class with no superclass.")
+                        )
+                    }
+                    ClassType::Subclass => {}
+                }
+                Ok(Expr::Super(SuperExpr { method, line }))
+            }
 
             _ => {
                 // cant parse sucessuflly
@@ -552,6 +1429,133 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// 'match (value) { 1 => "one", "x" | "y" => "letter", n if n > 9 => { print n; } _ => nil }'
+    fn match_expression(&mut self) -> Result<Expr, Err> {
+        let line = self.previous().line;
+        self.consume(Type::OpenParen, "Expect '(' after 'match'.")?;
+        let subject = self.expression()?;
+        self.consume(Type::CloseParen, "Expect ')' after match value.")?;
+        self.consume(Type::OpenBrace, "Expect '{' before match arms.")?;
+        let mut arms = vec![];
+        while !self.check(Type::CloseBrace) && !self.is_at_end() {
+            let pattern = self.pattern()?;
+            let (guard, body) = self.scoped(pattern_names(&pattern), |parser| {
+                let mut guard = None;
+                if parser.expect(vec![Type::If]) {
+                    guard = Some(parser.expression()?);
+                }
+                parser.consume(Type::FatArrow, "Expect '=>' after match pattern.")?;
+                // a '{' after '=>' starts a block, not a map
+                let body = match parser.expect(vec![Type::OpenBrace]) {
+                    true => ArmBody::Block(parser.block()),
+                    false => ArmBody::Expr(parser.expression()?),
+                };
+                Ok((guard, body))
+            })?;
+            let is_block = matches!(body, ArmBody::Block(_));
+            arms.push(MatchArm {
+                pattern,
+                guard,
+                body,
+            });
+            // the ',' between arms can be left out after a block
+            if !self.expect(vec![Type::Comma]) && !is_block {
+                break;
+            }
+        }
+        self.consume(Type::CloseBrace, "Expect '}' after match arms.")?;
+        Ok(Expr::Match(MatchExpr {
+            subject: Box::new(subject),
+            arms,
+            line,
+        }))
+    }
+
+    fn pattern(&mut self) -> Result<Pattern, Err> {
+        let mut alternatives = vec![self.single_pattern()?];
+        while self.expect(vec![Type::Pipe]) {
+            alternatives.push(self.single_pattern()?);
+        }
+        // the arm can only use names that every alternative binds
+        let names = |pattern: &Pattern| {
+            let mut names = pattern_names(pattern);
+            names.sort();
+            names.dedup();
+            names
+        };
+        if alternatives
+            .iter()
+            .any(|p| names(p) != names(&alternatives[0]))
+        {
+            let msg = "All alternatives of a pattern must bind the same names.";
+            self.errors
+                .push(Err::Parser(msg.into(), self.previous().line));
+        }
+        match alternatives.len() {
+            1 => Ok(alternatives.remove(0)),
+            _ => Ok(Pattern::Alternatives(alternatives)),
+        }
+    }
+
+    fn single_pattern(&mut self) -> Result<Pattern, Err> {
+        let typ = self.advance().typ.clone();
+        match typ {
+            Type::Underscore => Ok(Pattern::Wildcard),
+            // 'Shape.Circle(r)' or 'Shape.Empty' match a variant of an enum
+            Type::Identifier if self.check(Type::Dot) => {
+                let enum_name = self.previous().lexeme.to_string();
+                self.advance();
+                let token = self.consume(Type::Identifier, "Expect variant name after '.'.")?;
+                let variant = token.lexeme.to_string();
+                let mut fields = None;
+                if self.expect(vec![Type::OpenParen]) {
+                    let mut patterns = vec![];
+                    if !self.check(Type::CloseParen) {
+                        loop {
+                            patterns.push(self.pattern()?);
+                            if !self.expect(vec![Type::Comma]) {
+                                break;
+                            }
+                        }
+                    }
+                    self.consume(Type::CloseParen, "Expect ')' after variant fields.")?;
+                    fields = Some(patterns);
+                }
+                Ok(Pattern::Variant {
+                    enum_name,
+                    variant,
+                    fields,
+                })
+            }
+            Type::Identifier => Ok(Pattern::Binding(self.previous().lexeme.to_string())),
+            Type::True => Ok(Pattern::Literal(Value::Boolean(true))),
+            Type::False => Ok(Pattern::Literal(Value::Boolean(false))),
+            Type::Nil => Ok(Pattern::Literal(Value::Nil)),
+            Type::Number(nr) => Ok(Pattern::Literal(Value::Number(nr))),
+            Type::Integer(nr) => Ok(Pattern::Literal(Value::Integer(nr))),
+            Type::String(st) => Ok(Pattern::Literal(Value::String(st))),
+            Type::Minus => match self.advance().typ {
+                Type::Number(nr) => Ok(Pattern::Literal(Value::Number(-nr))),
+                Type::Integer(nr) => Ok(Pattern::Literal(Value::Integer(-nr))),
+                _ => Err(self.error_expr("Expect number after '-' in pattern.")),
+            },
+            Type::OpenBracket => {
+                let mut elements = vec![];
+                if !self.check(Type::CloseBracket) {
+                    loop {
+                        elements.push(self.pattern()?);
+                        if !self.expect(vec![Type::Comma]) {
+                            break;
+                        }
+                    }
+                }
+                self.consume(Type::CloseBracket, "Expect ']' after list pattern.")?;
+                Ok(Pattern::List(elements))
+            }
+            _ => Err(self.error_expr("Expect pattern in match arm.")),
+        }
+    }
+
     /// We expect the Type (advance and return expr if so). If not we return an error.
     fn consume(&mut self, typ: Type, msg: &str) -> Result<&Token, Err> {
         // TODO if we actually use ErrorExpr instead of Result<Expr>
@@ -604,6 +1608,7 @@ mod tests {
             left: Box::new(Expr::Literal(Value::Boolean(true))),
             token: TokenType::EqualEqual,
             right: Box::new(Expr::Literal(Value::Boolean(false))),
+            line: 1,
         });
 
         let expected = vec![Ok(Statement::ExprSt(expected))];
@@ -620,13 +1625,15 @@ mod tests {
         let ast = AST::new(tokens);
 
         let expected = Expr::Binary(BinaryExpr {
-            left: Box::new(Expr::Literal(Value::Number(1.0))),
+            left: Box::new(Expr::Literal(Value::Integer(1))),
             token: Type::Plus,
             right: Box::new(Expr::Binary(BinaryExpr {
-                left: Box::new(Expr::Literal(Value::Number(2.0))),
+                left: Box::new(Expr::Literal(Value::Integer(2))),
                 token: Type::Star,
-                right: Box::new(Expr::Literal(Value::Number(3.0))),
+                right: Box::new(Expr::Literal(Value::Integer(3))),
+                line: 1,
             })),
+            line: 1,
         });
 
         let expected = vec![Ok(Statement::ExprSt(expected))];
@@ -645,17 +1652,304 @@ mod tests {
         let expected = Expr::Binary(BinaryExpr {
             left: Box::new(Expr::Grouping(GroupingExpr {
                 expr: Box::new(Expr::Binary(BinaryExpr {
-                    left: Box::new(Expr::Literal(Value::Number(1.0))),
+                    left: Box::new(Expr::Literal(Value::Integer(1))),
                     token: Type::Minus,
-                    right: Box::new(Expr::Literal(Value::Number(2.0))),
+                    right: Box::new(Expr::Literal(Value::Integer(2))),
+                    line: 1,
                 })),
             })),
             token: Type::Slash,
-            right: Box::new(Expr::Literal(Value::Number(3.0))),
+            right: Box::new(Expr::Literal(Value::Integer(3))),
+            line: 1,
         });
 
         let expected = vec![Ok(Statement::ExprSt(expected))];
         assert_eq!(ast.root, expected);
         assert!(ast.errors.len() == 0);
     }
+
+    #[test]
+    fn property_assignment() {
+        // AST: <<this.a>.b = 1>
+        let s = new_scanner("class A { m() { this.a.b = 1; } }");
+        let (tokens, lexer_errs) = s.results();
+        assert!(lexer_errs.is_empty());
+        let ast = AST::new(tokens);
+
+        let expected = Expr::Set(SetExpr {
+            object: Box::new(Expr::Get(GetExpr {
+                object: Box::new(Expr::This(ThisExpr { line: 1 })),
+                name: "a".into(),
+                line: 1,
+            })),
+            name: "b".into(),
+            value: Box::new(Expr::Literal(Value::Integer(1))),
+            line: 1,
+        });
+        let expected = vec![Ok(Statement::ClassSt(ClassStatement {
+            name: "A".into(),
+            superclass: None,
+            methods: vec![FunctionStatement {
+                name: "m".into(),
+                params: vec![],
+                body: vec![Ok(Statement::ExprSt(expected))],
+                line: 1,
+            }],
+            line: 1,
+        }))];
+        assert_eq!(ast.root, expected);
+        assert!(ast.errors.is_empty());
+    }
+
+    #[test]
+    fn increment_and_compound_assignment() {
+        // AST: <<xs[0]++> + <i -= 2>>
+        let s = new_scanner("xs[0]++ + (i -= 2);");
+        let (tokens, lexer_errs) = s.results();
+        assert!(lexer_errs.is_empty());
+        let ast = AST::new(tokens);
+
+        let increment = Expr::CompoundAssign(CompoundAssignExpr {
+            target: Box::new(Expr::Index(IndexExpr {
+                object: Box::new(Expr::VarRead(VarReadExpr {
+                    name: "xs".into(),
+                    line: 1,
+                })),
+                index: Box::new(Expr::Literal(Value::Integer(0))),
+                line: 1,
+            })),
+            operator: Type::Plus,
+            value: Box::new(Expr::Literal(Value::Integer(1))),
+            postfix: true,
+            line: 1,
+        });
+        let compound = Expr::CompoundAssign(CompoundAssignExpr {
+            target: Box::new(Expr::VarRead(VarReadExpr {
+                name: "i".into(),
+                line: 1,
+            })),
+            operator: Type::Minus,
+            value: Box::new(Expr::Literal(Value::Integer(2))),
+            postfix: false,
+            line: 1,
+        });
+        let expected = Expr::Binary(BinaryExpr {
+            left: Box::new(increment),
+            token: Type::Plus,
+            right: Box::new(Expr::Grouping(GroupingExpr {
+                expr: Box::new(compound),
+            })),
+            line: 1,
+        });
+        assert_eq!(ast.root, vec![Ok(Statement::ExprSt(expected))]);
+        assert!(ast.errors.is_empty());
+
+        let s = new_scanner("1++;");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        assert_eq!(
+            ast.errors[0],
+            Err::Parser("Invalid target for '++'.".into(), 1)
+        );
+    }
+
+    #[test]
+    fn match_arms_and_patterns() {
+        let s = new_scanner("match (x) { 1 | -2 => \"a\", [y, _] if y => {} }");
+        let (tokens, lexer_errs) = s.results();
+        assert!(lexer_errs.is_empty());
+        let ast = AST::new(tokens);
+
+        let expected = Expr::Match(MatchExpr {
+            subject: Box::new(Expr::VarRead(VarReadExpr {
+                name: "x".into(),
+                line: 1,
+            })),
+            arms: vec![
+                MatchArm {
+                    pattern: Pattern::Alternatives(vec![
+                        Pattern::Literal(Value::Integer(1)),
+                        Pattern::Literal(Value::Integer(-2)),
+                    ]),
+                    guard: None,
+                    body: ArmBody::Expr(Expr::Literal(Value::String("a".into()))),
+                },
+                MatchArm {
+                    pattern: Pattern::List(vec![Pattern::Binding("y".into()), Pattern::Wildcard]),
+                    guard: Some(Expr::VarRead(VarReadExpr {
+                        name: "y".into(),
+                        line: 1,
+                    })),
+                    body: ArmBody::Block(vec![]),
+                },
+            ],
+            line: 1,
+        });
+        assert_eq!(ast.root, vec![Ok(Statement::ExprSt(expected))]);
+        assert!(ast.errors.is_empty());
+
+        let s = new_scanner("match (x) { 1 \"one\" }");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        assert_eq!(
+            ast.root[0],
+            Err(Err::Parser("Expect '=>' after match pattern.".into(), 1))
+        );
+    }
+
+    #[test]
+    fn default_rest_and_named_parameters() {
+        let s = new_scanner("fun f(a = 1, b) {}");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        assert_eq!(
+            ast.errors[0],
+            Err::Parser(
+                "Parameter 'b' without default can't follow ones with defaults.".into(),
+                1
+            )
+        );
+
+        let s = new_scanner("f(a: 1, 2);");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        assert_eq!(
+            ast.errors[0],
+            Err::Parser("Positional arguments can't follow named ones.".into(), 1)
+        );
+
+        let s = new_scanner("fun f(...rest, last) {}");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        assert_eq!(
+            ast.errors[0],
+            Err::Parser(
+                "Rest parameter 'rest' must be the last parameter.".into(),
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn destructuring_assignment_target() {
+        let s = new_scanner("[a, [b], ...rest] = xs; [a, 1] = xs;");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        let expected = Expr::DestructureAssign(DestructureAssignExpr {
+            target: Destructure::List {
+                elements: vec![
+                    Destructure::Name("a".into()),
+                    Destructure::List {
+                        elements: vec![Destructure::Name("b".into())],
+                        rest: None,
+                    },
+                ],
+                rest: Some("rest".into()),
+            },
+            value: Box::new(Expr::VarRead(VarReadExpr {
+                name: "xs".into(),
+                line: 1,
+            })),
+            line: 1,
+        });
+        assert_eq!(ast.root[0], Ok(Statement::ExprSt(expected)));
+        assert_eq!(
+            ast.errors[0],
+            Err::Parser("Invalid assignment target.".into(), 1)
+        );
+    }
+
+    #[test]
+    fn assigning_to_constants() {
+        let errors = |source: &str| {
+            let s = new_scanner(source);
+            let (tokens, _) = s.results();
+            AST::new(tokens).errors
+        };
+        let assign_error = Err::Parser("Can't assign to constant 'x'.".into(), 1);
+        assert_eq!(errors("const x = 1; x += 1;"), vec![assign_error.clone()]);
+        assert_eq!(errors("const x = 1; { x++; }"), vec![assign_error.clone()]);
+        assert_eq!(
+            errors("const x = 1; var y; [x, y] = [y, x];"),
+            vec![assign_error]
+        );
+        // shadowing names are no constants:
+        assert!(errors("const x = 1; fun f(x) { x = 2; } { var x; x = 3; }").is_empty());
+        assert!(
+            errors("const x = 1; for (x in [1]) { x = 2; } match (1) { x => x = 3 }").is_empty()
+        );
+        assert_eq!(
+            errors("const x;"),
+            vec![Err::Parser("Constant 'x' needs a value.".into(), 1)]
+        );
+        let redeclare_error = Err::Parser("Can't redeclare constant 'x'.".into(), 1);
+        assert_eq!(
+            errors("const x = 1; fun x() {}"),
+            vec![redeclare_error.clone()]
+        );
+        assert_eq!(errors("const x = 1; class x {}"), vec![redeclare_error]);
+        assert!(errors("const x = 1; { fun x() {} }").is_empty());
+    }
+
+    #[test]
+    fn enum_declaration_and_variant_patterns() {
+        let s = new_scanner("enum Shape { Circle(r), Rect(w, h), Empty, }");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        let expected = Statement::EnumSt {
+            name: "Shape".into(),
+            variants: vec![
+                ("Circle".into(), vec!["r".into()]),
+                ("Rect".into(), vec!["w".into(), "h".into()]),
+                ("Empty".into(), vec![]),
+            ],
+            line: 1,
+        };
+        assert_eq!(ast.root, vec![Ok(expected)]);
+        assert!(ast.errors.is_empty());
+
+        let s =
+            new_scanner("match (x) { Shape.Rect(w, _) | Shape.Circle(w) => w, Shape.Empty => 0 }");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        let Ok(Statement::ExprSt(Expr::Match(MatchExpr { arms, .. }))) = &ast.root[0] else {
+            panic!("expected a match, got {:?}", ast.root[0]);
+        };
+        let expected = Pattern::Alternatives(vec![
+            Pattern::Variant {
+                enum_name: "Shape".into(),
+                variant: "Rect".into(),
+                fields: Some(vec![Pattern::Binding("w".into()), Pattern::Wildcard]),
+            },
+            Pattern::Variant {
+                enum_name: "Shape".into(),
+                variant: "Circle".into(),
+                fields: Some(vec![Pattern::Binding("w".into())]),
+            },
+        ]);
+        assert_eq!(arms[0].pattern, expected);
+        let expected = Pattern::Variant {
+            enum_name: "Shape".into(),
+            variant: "Empty".into(),
+            fields: None,
+        };
+        assert_eq!(arms[1].pattern, expected);
+
+        let s = new_scanner("enum State { Idle, Idle }");
+        let (tokens, _) = s.results();
+        assert_eq!(
+            AST::new(tokens).errors,
+            vec![Err::Parser(
+                "Duplicate variant 'Idle' in enum 'State'.".into(),
+                1
+            )]
+        );
+
+        let s = new_scanner("enum S { A(x, x) }");
+        let (tokens, _) = s.results();
+        let ast = AST::new(tokens);
+        let error = Err::Parser("Duplicate field 'x' in variant 'S.A'.".into(), 1);
+        assert_eq!(ast.errors, vec![error.clone()]);
+        assert_eq!(ast.root, vec![Err(error)]);
+    }
 }
@@ -8,12 +8,12 @@
         - Represent blocks and local scope
 */
 
-use std::rc::Rc;
+use std::{collections::HashMap, rc::Rc};
 
 use crate::{
     environment::Environment,
-    expressions::{Expr, Function, Value},
-    interpreter::{execute_block, is_truthy},
+    expressions::{Class, Destructure, Enum, Expr, Function, IndexExpr, Value, VarReadExpr},
+    interpreter::{caught_value, execute_block, is_truthy, Iteration},
     types::Err,
 };
 
@@ -21,7 +21,20 @@ use crate::{
 pub enum Statement {
     ExprSt(Expr),
     PrintSt(Expr),
-    VariableSt(String, Expr),
+    /// 'var x = 1;' or 'const x = 1;'
+    VariableSt {
+        name: String,
+        value: Expr,
+        constant: bool,
+        line: usize,
+    },
+    /// 'var [a, ...rest] = xs;' or 'const {name, age} = record;'
+    DestructureSt {
+        target: Destructure,
+        value: Expr,
+        constant: bool,
+        line: usize,
+    },
     BlockSt(Vec<Result<Statement, Err>>),
     IfSt {
         condition: Expr,
@@ -31,19 +44,78 @@ pub enum Statement {
     While {
         condition: Expr,
         body: Box<Statement>,
+        /// the desugared for-loop increment, runs after each iteration (even after a 'continue')
+        increment: Option<Expr>,
+    },
+    /// 'for (item in iterable) body' each iteration binds item in a fresh scope
+    ForIn {
+        name: String,
+        iterable: Expr,
+        body: Box<Statement>,
+        line: usize,
     },
     FunctionSt(FunctionStatement),
+    ClassSt(ClassStatement),
+    /// 'enum Shape { Circle(r), Rect(w, h), Empty }' the variants with the names of their fields
+    EnumSt {
+        name: String,
+        variants: Vec<(String, Vec<String>)>,
+        line: usize,
+    },
+    /// 'delete xs[0];' or 'delete m["key"];'
+    DeleteSt(IndexExpr),
+    BreakSt,
+    ContinueSt,
+    TrySt(TryStatement),
+    ThrowSt {
+        value: Expr,
+        line: usize,
+    },
+    /// 'import "path/to/file.lox" as name;'
+    ImportSt {
+        path: String,
+        name: String,
+        line: usize,
+    },
+    /// 'return;' without a value returns nil
     ReturnSt {
         keyword: String,
-        value: Expr,
+        value: Option<Expr>,
     },
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct FunctionStatement {
     pub name: String,
-    pub params: Vec<String>,
+    pub params: Vec<Param>,
+    pub body: Vec<Result<Statement, Err>>,
+    pub line: usize,
+}
+
+/// a parameter 'name' or 'name = default'. The default gets evaluated on each call that leaves it out
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name: String,
+    pub default: Option<Expr>,
+    /// '...name' collects all left over arguments into a list, only allowed as last parameter
+    pub rest: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassStatement {
+    pub name: String,
+    pub superclass: Option<VarReadExpr>,
+    pub methods: Vec<FunctionStatement>,
+    pub line: usize,
+}
+
+/// 'try {...} catch (e) {...} finally {...}' either catch or finally can be left out
+#[derive(Debug, Clone, PartialEq)]
+pub struct TryStatement {
     pub body: Vec<Result<Statement, Err>>,
+    /// the name the caught error gets bound to and the handler-block
+    pub catch: Option<(String, Vec<Result<Statement, Err>>)>,
+    pub finally: Option<Vec<Result<Statement, Err>>>,
 }
 
 impl Statement {
@@ -52,8 +124,22 @@ impl Statement {
         match self {
             Self::ExprSt(expr) => execute_expr_statement(expr, current_env),
             Self::PrintSt(expr) => execute_print_statement(expr, current_env),
-            Self::VariableSt(name, initial_value) => {
-                execuate_var_statement(name, initial_value, current_env)
+            Self::VariableSt {
+                name,
+                value,
+                constant,
+                line,
+            } => execuate_var_statement(name, value, constant, line, current_env),
+            Self::DestructureSt {
+                target,
+                value,
+                constant,
+                line,
+            } => {
+                let value = value.evaluated(current_env.clone())?;
+                target.bind(value, line, &mut |name, element| {
+                    current_env.declare(name, element, constant, line)
+                })
             }
             Self::BlockSt(statements) => execute_block_statement(statements, current_env),
             Self::IfSt {
@@ -61,10 +147,39 @@ impl Statement {
                 then_,
                 else_,
             } => execute_if_statement(condition, then_, else_, current_env),
-            Self::While { condition, body } => {
-                execute_while_statement(condition, *body, current_env)
-            }
+            Self::While {
+                condition,
+                body,
+                increment,
+            } => execute_while_statement(condition, *body, increment, current_env),
+            Self::ForIn {
+                name,
+                iterable,
+                body,
+                line,
+            } => execute_for_in_statement(name, iterable, *body, line, current_env),
             Self::FunctionSt(fn_st) => execute_function_statement(fn_st, current_env),
+            Self::ClassSt(class_st) => execute_class_statement(class_st, current_env),
+            Self::EnumSt {
+                name,
+                variants,
+                line,
+            } => {
+                let enum_ = Enum {
+                    name: name.clone(),
+                    variants,
+                };
+                let enum_ = Expr::Literal(Value::Enum(Rc::new(enum_)));
+                current_env.declare(name, enum_, false, line)
+            }
+            Self::DeleteSt(target) => target.delete(current_env),
+            Self::BreakSt => Err(Err::Break),
+            Self::ContinueSt => Err(Err::Continue),
+            Self::TrySt(try_st) => execute_try_statement(try_st, current_env),
+            Self::ThrowSt { value, line } => Err(Err::Thrown(value.evaluated(current_env)?, line)),
+            Self::ImportSt { path, name, line } => {
+                execute_import_statement(path, name, line, current_env)
+            }
             Self::ReturnSt { keyword, value } => {
                 execute_return_statement(keyword, value, current_env)
             }
@@ -74,34 +189,161 @@ impl Statement {
 
 fn execute_return_statement(
     _keyword: String,
-    value: Expr,
+    value: Option<Expr>,
     env: Rc<Environment>,
 ) -> Result<(), Err> {
-    let mut return_val = Expr::Literal(Value::Nil);
-    if value != Expr::Literal(Value::Nil) {
-        return_val = value.evaluated(env)?;
-    }
+    let return_val = match value {
+        Some(value) => value.evaluated(env)?,
+        None => Expr::Literal(Value::Nil),
+    };
     Err(Err::ReturnValue(return_val))
 }
 
+/// binds the namespace of the (maybe already loaded) module to name
+fn execute_import_statement(
+    path: String,
+    name: String,
+    line: usize,
+    env: Rc<Environment>,
+) -> Result<(), Err> {
+    let root = env.root();
+    let Some(modules) = root.modules.clone() else {
+        return Err(Err::Interpreter(
+            format!("Can't import '{path}' without an interpreter."),
+            line,
+        ));
+    };
+    let module = modules.import(&path, line, root)?;
+    env.declare(name, Expr::Literal(Value::Module(module)), false, line)
+}
+
 /// a function is declared 'fun name(...params){ ...body; }'
 fn execute_function_statement(fn_st: FunctionStatement, env: Rc<Environment>) -> Result<(), Err> {
-    let FunctionStatement { name, .. } = fn_st.clone();
+    let FunctionStatement { name, line, .. } = fn_st.clone();
     let function = Expr::Literal(Value::Callable(Rc::new(Function::Declared {
         function_st: fn_st,
         closure: Rc::clone(&env),
+        is_initializer: false,
     })));
-    env.define(name, function);
-    Ok(())
+    env.declare(name, function, false, line)
+}
+
+/// a class is declared 'class Name { method(){...} ...}' or 'class Name < Superclass {...}'
+fn execute_class_statement(class_st: ClassStatement, env: Rc<Environment>) -> Result<(), Err> {
+    let ClassStatement {
+        name,
+        superclass,
+        methods,
+        line,
+    } = class_st;
+
+    let mut superclass_val = None;
+    if let Some(superclass) = superclass {
+        if superclass.name == name {
+            return Err(Err::Interpreter(
+                format!("Class '{name}' can't inherit from itself."),
+                line,
+            ));
+        }
+        match superclass.eval_with_env(env.clone())? {
+            Expr::Literal(Value::Class(class)) => superclass_val = Some(class),
+            _ => {
+                return Err(Err::Interpreter(
+                    format!("Superclass '{}' must be a class.", superclass.name),
+                    line,
+                ))
+            }
+        }
+    }
+
+    // methods of subclasses close over an extra env where 'super' points to the superclass:
+    let mut method_env = Rc::clone(&env);
+    if let Some(superclass) = &superclass_val {
+        method_env = Rc::new(Environment::new(Some(env.clone())));
+        let superclass = Expr::Literal(Value::Class(Rc::clone(superclass)));
+        method_env.define("super".into(), superclass);
+    }
+
+    let mut class_methods = HashMap::new();
+    for method in methods {
+        let function = Function::Declared {
+            is_initializer: method.name == "init",
+            function_st: method.clone(),
+            closure: Rc::clone(&method_env),
+        };
+        class_methods.insert(method.name, function);
+    }
+    let class = Class {
+        name: name.clone(),
+        superclass: superclass_val,
+        methods: class_methods,
+    };
+    env.declare(
+        name,
+        Expr::Literal(Value::Class(Rc::new(class))),
+        false,
+        line,
+    )
+}
+
+fn execute_try_statement(try_st: TryStatement, env: Rc<Environment>) -> Result<(), Err> {
+    let TryStatement {
+        body,
+        catch,
+        finally,
+    } = try_st;
+    let mut result = execute_block(env.clone(), body);
+
+    if let (Err(e), Some((name, handler))) = (&result, catch) {
+        if let Some(error_value) = caught_value(e.clone()) {
+            // the catch block gets its own scope with the error bound to the name
+            let catch_env = Rc::new(Environment::new(Some(env.clone())));
+            catch_env.define(name, error_value);
+            result = execute_block(catch_env, handler);
+        }
+    }
+    // finally always runs. Returns, breaks or errors coming from it win over the ones of try/catch
+    if let Some(finally) = finally {
+        execute_block(env, finally)?;
+    }
+    result
 }
 
 fn execute_while_statement(
     condition: Expr,
     body: Statement,
+    increment: Option<Expr>,
     env: Rc<Environment>,
 ) -> Result<(), Err> {
     while is_truthy(condition.evaluated(env.clone())?) {
-        body.clone().execute(env.clone())?;
+        match body.clone().execute(env.clone()) {
+            Err(Err::Break) => break,
+            Ok(()) | Err(Err::Continue) => {}
+            Err(e) => return Err(e),
+        }
+        if let Some(increment) = &increment {
+            increment.evaluated(env.clone())?;
+        }
+    }
+    Ok(())
+}
+
+fn execute_for_in_statement(
+    name: String,
+    iterable: Expr,
+    body: Statement,
+    line: usize,
+    env: Rc<Environment>,
+) -> Result<(), Err> {
+    let mut iteration = Iteration::new(iterable.evaluated(env.clone())?, env.clone(), line)?;
+    while let Some(item) = iteration.next(env.clone(), line)? {
+        let loop_env = Rc::new(Environment::new(Some(env.clone())));
+        loop_env.define(name.clone(), item);
+        match body.clone().execute(loop_env) {
+            Err(Err::Break) => break,
+            Ok(()) | Err(Err::Continue) => {}
+            Err(e) => return Err(e),
+        }
     }
     Ok(())
 }
@@ -120,20 +362,20 @@ fn execute_print_statement(expr: Expr, env: Rc<Environment>) -> Result<(), Err>
 fn execuate_var_statement(
     name: String,
     initial_value: Expr,
+    constant: bool,
+    line: usize,
     environment: Rc<Environment>,
 ) -> Result<(), Err> {
     // uninitialized will pass down a nil -> so they become nil;
     let value = initial_value.evaluated(environment.clone())?;
-    environment.define(name, value);
-    Ok(())
+    environment.declare(name, value, constant, line)
 }
 
 fn execute_block_statement(
     statements: Vec<Result<Statement, Err>>,
     env: Rc<Environment>,
 ) -> Result<(), Err> {
-    let _ = execute_block(env, statements); // it is only possible to return from functions in lox
-    Ok(())
+    execute_block(env, statements) // return values unwind trough blocks up to the next FunctionCall
 }
 
 fn execute_if_statement(
@@ -16,6 +16,11 @@ pub enum Err {
     Lexer(String, usize),
     Interpreter(String, usize),
     ReturnValue(Expr),
+    // 'throw value;' unwinds up to the next try-catch (value, line-of-throw)
+    Thrown(Expr, usize),
+    // break; and continue; unwind up to the next loop, just like return values do up to functions
+    Break,
+    Continue,
 }
 impl std::fmt::Display for Err {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -30,6 +35,11 @@ impl std::fmt::Display for Err {
                 "Interpreter-ERROR in line: {line} : {message}!"
             )),
             Err::ReturnValue(value) => f.write_fmt(format_args!("Return: {value}")),
+            Err::Thrown(value, line) => f.write_fmt(format_args!(
+                "Interpreter-ERROR in line: {line} : Uncaught {value}!"
+            )),
+            Err::Break => f.write_str("Break"),
+            Err::Continue => f.write_str("Continue"),
         }
     }
 }
@@ -57,10 +67,14 @@ impl fmt::Display for Token<'_> {
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     // single-character tokens
-    OpenParen, CloseParen, OpenBrace, CloseBrace,
-    Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    OpenParen, CloseParen, OpenBrace, CloseBrace, OpenBracket, CloseBracket,
+    Comma, Dot, Minus, Plus, Semicolon, Slash, Star, Colon, Percent,
+    Ampersand, Pipe, Caret, Tilde, Question, Underscore,
 
     // 1-2 character tokens
+    StarStar, TildeSlash, LessLess, GreaterGreater,
+    PlusEqual, MinusEqual, StarEqual, SlashEqual, PlusPlus, MinusMinus,
+    QuestionQuestion, FatArrow, DotDot, DotDotEqual, DotDotDot,
     Exclamation, ExclamationEqual,
     Equal, EqualEqual,
     Greater, GreaterEqual,
@@ -69,11 +83,14 @@ pub enum TokenType {
     // Literals
     Identifier,
     String(String),
+    /// the part of a string in front of an embedded ${expression}: "part ${"
+    Interpolation(String),
     Number(f64),
+    Integer(i64),
 
     // Keywords
-    And, Class, Else, False, Fun, For, If, Nil, Or,
-    Print, Return, Super, This, True, Var, While,
+    And, As, Break, Catch, Class, Const, Continue, Delete, Else, Enum, False, Finally, Fun, For, If, Import,
+    In, Match, Nil, Or, Print, Return, Super, This, Throw, True, Try, Var, While,
 
     EOF,
 }
@@ -86,17 +103,42 @@ impl fmt::Display for TokenType {
             TokenType::CloseParen => f.write_str(")"),
             TokenType::OpenBrace => f.write_str("{"),
             TokenType::CloseBrace => f.write_str("}"),
+            TokenType::OpenBracket => f.write_str("["),
+            TokenType::CloseBracket => f.write_str("]"),
             TokenType::Comma => f.write_str(","),
             TokenType::Dot => f.write_str("."),
+            TokenType::DotDot => f.write_str(".."),
+            TokenType::DotDotEqual => f.write_str("..="),
+            TokenType::DotDotDot => f.write_str("..."),
             TokenType::Minus => f.write_str("-"),
             TokenType::Plus => f.write_str("+"),
             TokenType::Semicolon => f.write_str(";"),
             TokenType::Slash => f.write_str("/"),
             TokenType::Star => f.write_str("*"),
+            TokenType::StarStar => f.write_str("**"),
+            TokenType::Percent => f.write_str("%"),
+            TokenType::TildeSlash => f.write_str("~/"),
+            TokenType::Ampersand => f.write_str("&"),
+            TokenType::Pipe => f.write_str("|"),
+            TokenType::Caret => f.write_str("^"),
+            TokenType::Tilde => f.write_str("~"),
+            TokenType::Question => f.write_str("?"),
+            TokenType::Underscore => f.write_str("_"),
+            TokenType::QuestionQuestion => f.write_str("??"),
+            TokenType::PlusEqual => f.write_str("+="),
+            TokenType::MinusEqual => f.write_str("-="),
+            TokenType::StarEqual => f.write_str("*="),
+            TokenType::SlashEqual => f.write_str("/="),
+            TokenType::PlusPlus => f.write_str("++"),
+            TokenType::MinusMinus => f.write_str("--"),
+            TokenType::LessLess => f.write_str("<<"),
+            TokenType::GreaterGreater => f.write_str(">>"),
+            TokenType::Colon => f.write_str(":"),
             TokenType::Exclamation => f.write_str("!"),
             TokenType::ExclamationEqual => f.write_str("!="),
             TokenType::Equal => f.write_str("="),
             TokenType::EqualEqual => f.write_str("=="),
+            TokenType::FatArrow => f.write_str("=>"),
             TokenType::Greater => f.write_str(">"),
             TokenType::GreaterEqual => f.write_str(">="),
             TokenType::Less => f.write_str("<"),
@@ -106,7 +148,9 @@ impl fmt::Display for TokenType {
                 f.write_str("TODO: Display for IDENTIFIER in types.TokenType.Display")
             }
             TokenType::String(s) => f.write_str(s),
+            TokenType::Interpolation(s) => write!(f, "{s}${{"),
             TokenType::Number(n) => n.fmt(f),
+            TokenType::Integer(n) => n.fmt(f),
 
             // just using default {:?} for the following:
             // TokenType::And => f.write_str("("),
@@ -0,0 +1,129 @@
+use std::{cell::RefCell, fs, rc::Rc};
+
+use rs_interpreter::{Arity, Err, Interpreter, Value};
+
+// registers a variadic native that stores every argument it gets called with
+fn recording_interpreter() -> (Interpreter, Rc<RefCell<Vec<Value>>>) {
+    let recorded = Rc::new(RefCell::new(vec![]));
+    let captured = Rc::clone(&recorded);
+    let mut interpreter = Interpreter::new();
+    interpreter.register_native("record", Arity::Variadic(0), move |args| {
+        captured.borrow_mut().extend_from_slice(args);
+        Ok(Value::Nil)
+    });
+    (interpreter, recorded)
+}
+
+#[test]
+fn natives_receive_arguments() {
+    let (mut interpreter, recorded) = recording_interpreter();
+    interpreter.register_native("double", Arity::Fixed(1), |args| match &args[0] {
+        Value::Integer(n) => Ok(Value::Integer(n * 2)),
+        Value::Number(n) => Ok(Value::Number(n * 2.0)),
+        _ => Err("double() expects a number".into()),
+    });
+    interpreter
+        .run("record(double(21), \"a\", nil);".into(), false)
+        .unwrap();
+    assert_eq!(
+        *recorded.borrow(),
+        vec![Value::Integer(42), Value::String("a".into()), Value::Nil]
+    );
+}
+
+#[test]
+fn natives_keep_captured_state() {
+    let (mut interpreter, recorded) = recording_interpreter();
+    let counter = Rc::new(RefCell::new(0.0));
+    interpreter.register_native("next", Arity::Fixed(0), move |_| {
+        *counter.borrow_mut() += 1.0;
+        Ok(Value::Number(*counter.borrow()))
+    });
+    interpreter.run("next(); next();".into(), false).unwrap();
+    // the global scope survives between runs:
+    interpreter
+        .run("record(next(), len([1, 2]));".into(), false)
+        .unwrap();
+    assert_eq!(
+        *recorded.borrow(),
+        vec![Value::Number(3.0), Value::Integer(2)]
+    );
+}
+
+#[test]
+fn uncaught_errors_are_returned_to_the_host() {
+    let (interpreter, recorded) = recording_interpreter();
+    let result = interpreter.run("record(1); 1 / 0; record(2);".into(), false);
+    assert!(matches!(result, Err(Err::Interpreter(_, 1))));
+    // the interpreter stays usable, constants of earlier runs are still protected:
+    interpreter
+        .run("const x = 3; record(x);".into(), false)
+        .unwrap();
+    let result = interpreter.run("fun x() {}".into(), false);
+    assert_eq!(
+        result,
+        Err(Err::Interpreter("Can't redeclare constant 'x'.".into(), 1))
+    );
+    assert_eq!(
+        *recorded.borrow(),
+        vec![Value::Integer(1), Value::Integer(3)]
+    );
+}
+
+#[test]
+fn interpreters_load_their_own_modules() {
+    let import = r#"import "tests/modules/lib/counter.lox" as counter; counter.increment();"#;
+    let (first, first_recorded) = recording_interpreter();
+    first.run(import.into(), false).unwrap();
+    first.run("counter.increment();".into(), false).unwrap();
+    // a second interpreter starts the module over, calling its own natives:
+    let (second, second_recorded) = recording_interpreter();
+    second.run(import.into(), false).unwrap();
+    assert_eq!(
+        *first_recorded.borrow(),
+        vec![Value::Integer(1), Value::Integer(2)]
+    );
+    assert_eq!(*second_recorded.borrow(), vec![Value::Integer(1)]);
+}
+
+#[test]
+fn running_a_file_that_is_already_running_fails() {
+    // main.lox runs itself again trough a native, then imports a file next to it
+    let dir = std::env::temp_dir().join(format!("rerun_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let main = dir.join("main.lox");
+    fs::write(
+        &main,
+        "record(rerun());\nimport \"helper.lox\" as helper;\nrecord(helper.value);",
+    )
+    .unwrap();
+    fs::write(dir.join("helper.lox"), "var value = 7;").unwrap();
+
+    let (mut interpreter, recorded) = recording_interpreter();
+    let this: Rc<RefCell<Option<Rc<Interpreter>>>> = Rc::new(RefCell::new(None));
+    let handle = Rc::clone(&this);
+    let path = main.clone();
+    interpreter.register_native("rerun", Arity::Fixed(0), move |_| {
+        let interpreter = handle.borrow().clone().unwrap();
+        match interpreter.run_file(&path, false) {
+            Err(e) => Ok(Value::String(e.to_string())),
+            Ok(()) => Ok(Value::Nil),
+        }
+    });
+    let interpreter = Rc::new(interpreter);
+    *this.borrow_mut() = Some(Rc::clone(&interpreter));
+    let result = interpreter.run_file(&main, false);
+    *this.borrow_mut() = None;
+    fs::remove_dir_all(&dir).unwrap();
+
+    // the import after the failed rerun still resolves next to main.lox
+    result.unwrap();
+    let error = format!(
+        "Interpreter-ERROR in line: 0 : Circular import of '{}'.!",
+        main.display()
+    );
+    assert_eq!(
+        *recorded.borrow(),
+        vec![Value::String(error), Value::Integer(7)]
+    );
+}